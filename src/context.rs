@@ -0,0 +1,325 @@
+use std::fmt;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::number::RoundingMode;
+
+pub const MAX_PRECISION: i32 = 10_000;
+pub const MAX_DISPLAY_LIMIT: usize = 10_000;
+pub const MAX_RADIX: u32 = 36; // Digits go up to Z
+
+// Unit in which trigonometric functions take and return angles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum AngleUnit {
+	#[default]
+	Radians,
+	Degrees,
+	Gradians,
+}
+
+impl AngleUnit {
+	pub const ALL: [AngleUnit; 3] = [
+		AngleUnit::Radians,
+		AngleUnit::Degrees,
+		AngleUnit::Gradians,
+	];
+
+	// Size of half a turn in this unit, or None for radians where it is pi
+	pub fn half_turn(self) -> Option<u32> {
+		match self {
+			AngleUnit::Radians => None,
+			AngleUnit::Degrees => Some(180),
+			AngleUnit::Gradians => Some(200),
+		}
+	}
+}
+
+impl fmt::Display for AngleUnit {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			AngleUnit::Radians => "Radians",
+			AngleUnit::Degrees => "Degrees",
+			AngleUnit::Gradians => "Gradians",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+// How exact fractions are shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FractionStyle {
+	#[default]
+	Decimal,
+	Improper,
+	Mixed,
+}
+
+impl FractionStyle {
+	pub const ALL: [FractionStyle; 3] = [
+		FractionStyle::Decimal,
+		FractionStyle::Improper,
+		FractionStyle::Mixed,
+	];
+}
+
+impl fmt::Display for FractionStyle {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			FractionStyle::Decimal => "Decimal",
+			FractionStyle::Improper => "7/4",
+			FractionStyle::Mixed => "1 3/4",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+// Width of the two's-complement words that bitwise operators and programmer mode work on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum WordSize {
+	Bits8,
+	Bits16,
+	Bits32,
+	#[default]
+	Bits64,
+	Bits128,
+	Unbounded,
+}
+
+impl WordSize {
+	pub const ALL: [WordSize; 6] = [
+		WordSize::Bits8,
+		WordSize::Bits16,
+		WordSize::Bits32,
+		WordSize::Bits64,
+		WordSize::Bits128,
+		WordSize::Unbounded,
+	];
+
+	// Number of bits, or None when integers have no fixed width
+	pub fn bits(self) -> Option<u32> {
+		match self {
+			WordSize::Bits8 => Some(8),
+			WordSize::Bits16 => Some(16),
+			WordSize::Bits32 => Some(32),
+			WordSize::Bits64 => Some(64),
+			WordSize::Bits128 => Some(128),
+			WordSize::Unbounded => None,
+		}
+	}
+}
+
+impl fmt::Display for WordSize {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self.bits() {
+			Some(bits) => write!(f, "{}-bit", bits),
+			None => write!(f, "Unbounded"),
+		}
+	}
+}
+
+// Stop signal for a calculation running in the background. Clones share the signal,
+// so the GUI keeps one and the evaluation checks another.
+#[derive(Debug, Clone, Default)]
+pub struct Cancellation(Arc<AtomicBool>);
+
+impl Cancellation {
+	pub fn cancel(&self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+
+	// Long-running loops call this regularly and stop with its error once cancelled
+	pub fn check(&self) -> Result<(), String> {
+		if self.0.load(Ordering::Relaxed) {
+			return Err("Cancelled".to_string());
+		}
+		Ok(())
+	}
+}
+
+// Two handles are equal when they control the same calculation
+impl PartialEq for Cancellation {
+	fn eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.0, &other.0)
+	}
+}
+
+// Arithmetic and display settings threaded through every evaluation,
+// in the spirit of Python's decimal contexts
+#[derive(Debug, Clone, PartialEq)]
+pub struct Context {
+	pub precision: i32, // Decimal places kept by inexact operations
+	pub rounding: RoundingMode,
+	pub display_limit: usize, // Widest result; longer ones are rounded to fit, or scientific when they cannot be
+	pub scientific_digits: usize, // Digits after the point in scientific notation
+	pub angle_unit: AngleUnit,
+	pub exact: bool, // Keep rational results as exact fractions
+	pub fraction_style: FractionStyle,
+	pub polar: bool, // Show complex results as modulus and angle
+	pub radix: u32, // Base of displayed results, from 2 to MAX_RADIX
+	pub word_size: WordSize,
+	pub signed: bool, // Read words in two's complement rather than as unsigned
+	pub emulate_f64: bool, // Evaluate with f64 arithmetic, as compiled Rust code would
+	pub cancellation: Cancellation, // Not saved; each calculation gets its own
+}
+
+impl Default for Context {
+	fn default() -> Self {
+		Self {
+			precision: 15,
+			rounding: RoundingMode::default(),
+			display_limit: 25,
+			scientific_digits: 10,
+			angle_unit: AngleUnit::default(),
+			exact: true,
+			fraction_style: FractionStyle::default(),
+			polar: false,
+			radix: 10,
+			word_size: WordSize::default(),
+			signed: true,
+			emulate_f64: false,
+			cancellation: Cancellation::default(),
+		}
+	}
+}
+
+impl Context {
+	// Working precisions derived from the setting never go below zero places, which
+	// the fixed-point conversions cannot represent
+	pub fn with_precision(&self, precision: i32) -> Self {
+		Self { precision: precision.max(0), ..self.clone() }
+	}
+
+	pub fn with_rounding(&self, rounding: RoundingMode) -> Self {
+		Self { rounding, ..self.clone() }
+	}
+
+	// Loads the saved settings, falling back to defaults for anything missing or invalid
+	pub fn load() -> Self {
+		let mut ctx = Self::default();
+		let Some(contents) = settings_path().and_then(|path| fs::read_to_string(path).ok()) else {
+			return ctx;
+		};
+
+		for line in contents.lines() {
+			let Some((key, value)) = line.split_once('=') else {
+				continue;
+			};
+			let value = value.trim();
+			match key.trim() {
+				"precision" => {
+					if let Ok(precision) = value.parse::<i32>()
+						&& (0..=MAX_PRECISION).contains(&precision) {
+						ctx.precision = precision;
+					}
+				}
+				"rounding" => {
+					if let Some(mode) = RoundingMode::ALL.iter().find(|mode| format!("{:?}", mode) == value) {
+						ctx.rounding = *mode;
+					}
+				}
+				"display_limit" => {
+					if let Ok(limit) = value.parse::<usize>()
+						&& (1..=MAX_DISPLAY_LIMIT).contains(&limit) {
+						ctx.display_limit = limit;
+					}
+				}
+				"scientific_digits" => {
+					if let Ok(digits) = value.parse::<usize>()
+						&& digits <= MAX_DISPLAY_LIMIT {
+						ctx.scientific_digits = digits;
+					}
+				}
+				"angle_unit" => {
+					if let Some(unit) = AngleUnit::ALL.iter().find(|unit| format!("{:?}", unit) == value) {
+						ctx.angle_unit = *unit;
+					}
+				}
+				"exact" => {
+					if let Ok(exact) = value.parse::<bool>() {
+						ctx.exact = exact;
+					}
+				}
+				"fraction_style" => {
+					if let Some(style) = FractionStyle::ALL.iter().find(|style| format!("{:?}", style) == value) {
+						ctx.fraction_style = *style;
+					}
+				}
+				"polar" => {
+					if let Ok(polar) = value.parse::<bool>() {
+						ctx.polar = polar;
+					}
+				}
+				"radix" => {
+					if let Ok(radix) = value.parse::<u32>()
+						&& (2..=MAX_RADIX).contains(&radix) {
+						ctx.radix = radix;
+					}
+				}
+				"word_size" => {
+					if let Some(size) = WordSize::ALL.iter().find(|size| format!("{:?}", size) == value) {
+						ctx.word_size = *size;
+					}
+				}
+				"signed" => {
+					if let Ok(signed) = value.parse::<bool>() {
+						ctx.signed = signed;
+					}
+				}
+				"emulate_f64" => {
+					if let Ok(emulate) = value.parse::<bool>() {
+						ctx.emulate_f64 = emulate;
+					}
+				}
+				_ => {}
+			}
+		}
+
+		ctx
+	}
+
+	pub fn save(&self) -> Result<(), String> {
+		let path = settings_path().ok_or("No settings directory available")?;
+		if let Some(dir) = path.parent() {
+			fs::create_dir_all(dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+		}
+
+		let contents = format!(
+			"precision={}\nrounding={:?}\ndisplay_limit={}\nscientific_digits={}\nangle_unit={:?}\nexact={}\nfraction_style={:?}\npolar={}\nradix={}\nword_size={:?}\nsigned={}\nemulate_f64={}\n",
+			self.precision, self.rounding, self.display_limit, self.scientific_digits, self.angle_unit,
+			self.exact, self.fraction_style, self.polar, self.radix, self.word_size, self.signed,
+			self.emulate_f64
+		);
+		fs::write(&path, contents).map_err(|e| format!("Cannot write {}: {}", path.display(), e))
+	}
+}
+
+fn settings_path() -> Option<PathBuf> {
+	let base = std::env::var_os("APPDATA")
+		.or_else(|| std::env::var_os("XDG_CONFIG_HOME"))
+		.map(PathBuf::from)
+		.or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+	Some(base.join("calc").join("settings.conf"))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn derived_precision_never_goes_negative() {
+		let ctx = Context { precision: 1, ..Context::default() };
+		assert_eq!(ctx.with_precision(ctx.precision - 2).precision, 0);
+	}
+
+	#[test]
+	fn cancellation_is_shared_by_clones() {
+		let cancellation = Cancellation::default();
+		let copy = cancellation.clone();
+		assert!(copy.check().is_ok());
+		cancellation.cancel();
+		assert_eq!(copy.check(), Err("Cancelled".to_string()));
+		assert_ne!(cancellation, Cancellation::default());
+	}
+}
@@ -0,0 +1,202 @@
+use num_traits::ToPrimitive;
+use std::f64::consts::{E, PI};
+
+use crate::context::{AngleUnit, Context};
+use crate::number::{Float, FloatFormat, Rational, parse_number};
+use crate::parser::{BinaryOp, Expr, UnaryOp};
+use crate::value::Value;
+
+// Evaluates the way compiled Rust code computes with f64: every literal is rounded to the
+// nearest f64, every operation rounds again, and functions come from the standard library.
+// The result reads as Rust prints it, the shortest decimal that converts back to the same f64.
+pub fn evaluate_f64(expr: &Expr, ctx: &Context) -> Result<Value, String> {
+	let x = evaluate(expr, ctx)?;
+	if !x.is_finite() {
+		return Err(format!("The f64 result is {}", x));
+	}
+	Ok(Value::Decimal(parse_number(&format!("{:?}", x))?))
+}
+
+// The literals of an expression that f64 cannot hold exactly, with what they become.
+// Most surprises in f64 results start with these, like 0.1 being slightly more than 0.1.
+pub fn inexact_literals(expr: &Expr) -> Vec<(Rational, Float)> {
+	let mut literals = Vec::new();
+	collect_literals(expr, &mut literals);
+	literals
+}
+
+fn collect_literals(expr: &Expr, literals: &mut Vec<(Rational, Float)>) {
+	let value = match expr {
+		Expr::Number(value) | Expr::Angle(value) | Expr::Imaginary(value) => value.to_rational(),
+		Expr::Fraction(value) => value.clone(),
+		Expr::Unary(_, operand) => return collect_literals(operand, literals),
+		Expr::Binary(_, lhs, rhs) => {
+			collect_literals(lhs, literals);
+			return collect_literals(rhs, literals);
+		}
+		Expr::Call(_, args) => {
+			for arg in args {
+				collect_literals(arg, literals);
+			}
+			return;
+		}
+		Expr::Constant(_) => return,
+	};
+	let float = Float::nearest(&value, FloatFormat::Binary64);
+	if float.to_rational().as_ref() != Some(&value) && !literals.iter().any(|(literal, _)| *literal == value) {
+		literals.push((value, float));
+	}
+}
+
+fn evaluate(expr: &Expr, ctx: &Context) -> Result<f64, String> {
+	match expr {
+		Expr::Number(value) => Ok(nearest(&value.to_rational())),
+		Expr::Fraction(value) => Ok(nearest(value)),
+		Expr::Angle(arcseconds) => Ok(from_degrees(nearest(&arcseconds.to_rational()) / 3600.0, ctx)),
+		Expr::Imaginary(_) => Err(unavailable("Complex numbers")),
+		Expr::Unary(UnaryOp::Negate, operand) => Ok(-evaluate(operand, ctx)?),
+		Expr::Unary(UnaryOp::BitNot, _) => Err(unavailable("Bitwise operators")),
+		Expr::Binary(op, lhs, rhs) => {
+			let a = evaluate(lhs, ctx)?;
+			let b = evaluate(rhs, ctx)?;
+			match op {
+				BinaryOp::Add => Ok(a + b),
+				BinaryOp::Subtract => Ok(a - b),
+				BinaryOp::Multiply => Ok(a * b),
+				BinaryOp::Divide => Ok(a / b),
+				BinaryOp::FloorDivide => Ok((a / b).floor()),
+				BinaryOp::Modulo => Ok(floored_modulo(a, b)),
+				BinaryOp::Power => Ok(a.powf(b)),
+				BinaryOp::Polar => Err(unavailable("Complex numbers")),
+				_ => Err(unavailable("Bitwise operators")),
+			}
+		}
+		Expr::Call(name, args) => {
+			let values = args.iter()
+				.map(|arg| evaluate(arg, ctx))
+				.collect::<Result<Vec<_>, _>>()?;
+			call(name, &values, ctx)
+		}
+		Expr::Constant(name) => match name.as_str() {
+			"pi" | "π" => Ok(PI),
+			"e" => Ok(E),
+			"phi" | "φ" => Ok(1.618033988749895),
+			"euler_gamma" | "γ" => Ok(0.5772156649015329),
+			"i" => Err(unavailable("Complex numbers")),
+			_ => Err(format!("Unknown constant '{}'", name)),
+		},
+	}
+}
+
+fn call(name: &str, args: &[f64], ctx: &Context) -> Result<f64, String> {
+	let value = match (name, args) {
+		("sqrt", [x]) => x.sqrt(),
+		// Roots of negative numbers are NaN here, as they are with powf
+		("root", [x, n]) => x.powf(1.0 / n),
+		("exp", [x]) => x.exp(),
+		("ln", [x]) => x.ln(),
+		("log10" | "log", [x]) => x.log10(),
+		("log2", [x]) => x.log2(),
+		("log", [x, base]) => x.log(*base),
+		("sin", [x]) => to_radians(*x, ctx).sin(),
+		("cos", [x]) => to_radians(*x, ctx).cos(),
+		("tan", [x]) => to_radians(*x, ctx).tan(),
+		("sec", [x]) => 1.0 / to_radians(*x, ctx).cos(),
+		("csc", [x]) => 1.0 / to_radians(*x, ctx).sin(),
+		("cot", [x]) => 1.0 / to_radians(*x, ctx).tan(),
+		("asin", [x]) => from_radians(x.asin(), ctx),
+		("acos", [x]) => from_radians(x.acos(), ctx),
+		("atan", [x]) => from_radians(x.atan(), ctx),
+		("atan2", [y, x]) => from_radians(y.atan2(*x), ctx),
+		("sinh", [x]) => x.sinh(),
+		("cosh", [x]) => x.cosh(),
+		("tanh", [x]) => x.tanh(),
+		("asinh", [x]) => x.asinh(),
+		("acosh", [x]) => x.acosh(),
+		("atanh", [x]) => x.atanh(),
+		("abs", [x]) => x.abs(),
+		("rem", [a, b]) => a % b,
+		_ => {
+			let plural = if args.len() == 1 { "" } else { "s" };
+			return Err(format!("{}() with {} argument{} is not available when emulating f64", name, args.len(), plural));
+		}
+	};
+	Ok(value)
+}
+
+// The f64 nearest to x, as the compiler rounds a literal
+fn nearest(x: &Rational) -> f64 {
+	let bits = Float::nearest(x, FloatFormat::Binary64).to_bits();
+	f64::from_bits(bits.to_u64().unwrap_or_default())
+}
+
+// Rust's trigonometric functions work in radians, so angles in other units are converted
+// first, as code would with to_radians()
+fn to_radians(x: f64, ctx: &Context) -> f64 {
+	match ctx.angle_unit {
+		AngleUnit::Radians => x,
+		AngleUnit::Degrees => x.to_radians(),
+		AngleUnit::Gradians => x * (PI / 200.0),
+	}
+}
+
+fn from_radians(x: f64, ctx: &Context) -> f64 {
+	match ctx.angle_unit {
+		AngleUnit::Radians => x,
+		AngleUnit::Degrees => x.to_degrees(),
+		AngleUnit::Gradians => x * (200.0 / PI),
+	}
+}
+
+fn from_degrees(x: f64, ctx: &Context) -> f64 {
+	match ctx.angle_unit {
+		AngleUnit::Radians => x.to_radians(),
+		AngleUnit::Degrees => x,
+		AngleUnit::Gradians => x / 0.9,
+	}
+}
+
+// Remainder with the sign of the divisor, like the mod operator; Rust's % keeps the sign
+// of the dividend
+fn floored_modulo(a: f64, b: f64) -> f64 {
+	let remainder = a % b;
+	if remainder != 0.0 && (remainder < 0.0) != (b < 0.0) { remainder + b } else { remainder }
+}
+
+fn unavailable(what: &str) -> String {
+	format!("{} are not available when emulating f64", what)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::calculate;
+
+	fn emulate(input: &str) -> String {
+		calculate(input, &Context { emulate_f64: true, ..Context::default() })
+	}
+
+	#[test]
+	fn results_match_rust() {
+		assert_eq!(emulate("0.1 + 0.2"), format!("{:?}", 0.1f64 + 0.2));
+		assert_eq!(emulate("2^3/4"), "2.0");
+		assert_eq!(emulate("1/3"), format!("{:?}", 1.0f64 / 3.0));
+		assert_eq!(emulate("-7 mod 3"), "2.0");
+		assert_eq!(emulate("rem(-7, 3)"), "-1.0");
+	}
+
+	#[test]
+	fn non_finite_results_and_unsupported_operations() {
+		assert_eq!(emulate("1e300 * 1e10"), "Error: The f64 result is inf");
+		assert_eq!(emulate("sqrt(-1)"), "Error: The f64 result is NaN");
+		assert!(emulate("3 & 1").starts_with("Error:"));
+	}
+
+	#[test]
+	fn lists_rounded_literals_once() {
+		let ast = crate::parser::parse(crate::lexer::tokenize("0.1 + 0.25 + 0.1 * 3").unwrap()).unwrap();
+		let literals = inexact_literals(&ast);
+		assert_eq!(literals.len(), 1);
+		assert_eq!(literals[0].0, Rational::new(1.into(), 10.into()).unwrap());
+	}
+}
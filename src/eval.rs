@@ -0,0 +1,338 @@
+use num_bigint::BigInt;
+
+use crate::context::Context;
+use crate::number::{BigNumber, Complex, Rational};
+use crate::parser::{BinaryOp, Expr, UnaryOp};
+use crate::value::Value;
+
+pub fn evaluate(expr: &Expr, ctx: &Context) -> Result<Value, String> {
+	match expr {
+		Expr::Number(value) => Value::from_literal(value, ctx),
+		Expr::Fraction(value) => Ok(Value::from_fraction(value, ctx)),
+		Expr::Imaginary(value) => Ok(Value::from_complex(Complex::from_imaginary(value.clone()))),
+		Expr::Angle(arcseconds) => angle(arcseconds, ctx),
+		Expr::Unary(op, operand) => {
+			// A negated fraction literal is rounded as a whole, so -1 1/3 rounds like -4/3
+			if let (UnaryOp::Negate, Expr::Fraction(value)) = (op, operand.as_ref()) {
+				return Ok(Value::from_fraction(&value.negate(), ctx));
+			}
+			// A factorization from factor() takes part in further calculations as its product
+			let value = evaluate(operand, ctx)?.expand(ctx);
+			match op {
+				UnaryOp::Negate => value.negate(),
+				UnaryOp::BitNot => Value::from_literal(&value.to_decimal(ctx)?.bit_not(ctx)?, ctx),
+			}
+		}
+		Expr::Binary(op, lhs, rhs) => {
+			let a = evaluate(lhs, ctx)?.expand(ctx);
+			let b = evaluate(rhs, ctx)?.expand(ctx);
+			apply_binary(*op, &a, &b, ctx)
+		}
+		Expr::Call(name, args) => {
+			let values = args.iter()
+				.map(|arg| evaluate(arg, ctx).map(|value| value.expand(ctx)))
+				.collect::<Result<Vec<_>, _>>()?;
+			if let Some(result) = call_value_function(name, &values, ctx) {
+				return result;
+			}
+			if is_complex_call(name, &values) {
+				return call_complex(name, &values, ctx).map(Value::from_complex);
+			}
+			// Other functions work on decimals, so exact arguments are rounded first
+			let values = values.iter()
+				.map(|value| value.to_decimal(ctx))
+				.collect::<Result<Vec<_>, _>>()?;
+			let result = call_function(name, &values, ctx)?;
+			// Combinatorial functions give exact integers, which stay exact like literals
+			if is_integer_function(name) {
+				Value::from_literal(&result, ctx)
+			} else {
+				Ok(Value::Decimal(result))
+			}
+		}
+		Expr::Constant(name) => constant(name, ctx),
+	}
+}
+
+fn apply_binary(op: BinaryOp, a: &Value, b: &Value, ctx: &Context) -> Result<Value, String> {
+	match op {
+		BinaryOp::Add => a.add(b, ctx),
+		BinaryOp::Subtract => a.subtract(b, ctx),
+		BinaryOp::Multiply => a.multiply(b, ctx),
+		BinaryOp::Divide => a.divide(b, ctx),
+		BinaryOp::FloorDivide => a.floor_divide(b, ctx),
+		BinaryOp::Modulo => a.modulo(b, ctx),
+		BinaryOp::Power => a.power(b, ctx),
+		BinaryOp::Polar => a.polar(b, ctx),
+		BinaryOp::BitAnd => bitwise(a, b, ctx, BigNumber::bit_and),
+		BinaryOp::BitOr => bitwise(a, b, ctx, BigNumber::bit_or),
+		BinaryOp::BitXor => bitwise(a, b, ctx, BigNumber::bit_xor),
+		BinaryOp::ShiftLeft => bitwise(a, b, ctx, BigNumber::shift_left),
+		BinaryOp::ShiftRight => bitwise(a, b, ctx, BigNumber::shift_right),
+	}
+}
+
+type WordOperation = fn(&BigNumber, &BigNumber, &Context) -> Result<BigNumber, String>;
+
+// Bitwise operators work on integers, and their results stay exact like literals
+fn bitwise(a: &Value, b: &Value, ctx: &Context, operation: WordOperation) -> Result<Value, String> {
+	let result = operation(&a.to_decimal(ctx)?, &b.to_decimal(ctx)?, ctx)?;
+	Value::from_literal(&result, ctx)
+}
+
+// Degrees and gradians are whole fractions of the arcseconds in a literal, radians are not
+fn angle(arcseconds: &BigNumber, ctx: &Context) -> Result<Value, String> {
+	match ctx.angle_unit.half_turn() {
+		Some(half_turn) if ctx.exact => {
+			let per_unit = Rational::from_integer(BigInt::from(648_000 / half_turn));
+			Ok(Value::Exact(arcseconds.to_rational().divide(&per_unit)?))
+		}
+		_ => BigNumber::from_arcseconds(arcseconds, ctx).map(Value::Decimal),
+	}
+}
+
+fn call_function(name: &str, args: &[BigNumber], ctx: &Context) -> Result<BigNumber, String> {
+	match name {
+		"sqrt" => {
+			expect_args(name, args, 1)?;
+			args[0].sqrt(ctx)
+		}
+		"root" => {
+			expect_args(name, args, 2)?;
+			let degree = args[1].to_u32()
+				.ok_or("Root degree must be a positive integer")?;
+			args[0].nth_root(degree, ctx)
+		}
+		"exp" => {
+			expect_args(name, args, 1)?;
+			args[0].exp(ctx)
+		}
+		"ln" => {
+			expect_args(name, args, 1)?;
+			args[0].ln(ctx)
+		}
+		"log10" => {
+			expect_args(name, args, 1)?;
+			args[0].log10(ctx)
+		}
+		"log2" => {
+			expect_args(name, args, 1)?;
+			args[0].log2(ctx)
+		}
+		// log(x) is the common logarithm, log(x, b) takes an explicit base
+		"log" => match args {
+			[x] => x.log10(ctx),
+			[x, base] => x.log(base, ctx),
+			_ => Err(format!("log() takes 1 or 2 arguments, got {}", args.len())),
+		},
+		"sin" | "cos" | "tan" | "sec" | "csc" | "cot" | "asin" | "acos" | "atan" => {
+			expect_args(name, args, 1)?;
+			let x = &args[0];
+			match name {
+				"sin" => x.sin(ctx),
+				"cos" => x.cos(ctx),
+				"tan" => x.tan(ctx),
+				"sec" => x.sec(ctx),
+				"csc" => x.csc(ctx),
+				"cot" => x.cot(ctx),
+				"asin" => x.asin(ctx),
+				"acos" => x.acos(ctx),
+				_ => x.atan(ctx),
+			}
+		}
+		"sinh" | "cosh" | "tanh" | "asinh" | "acosh" | "atanh" => {
+			expect_args(name, args, 1)?;
+			let x = &args[0];
+			match name {
+				"sinh" => x.sinh(ctx),
+				"cosh" => x.cosh(ctx),
+				"tanh" => x.tanh(ctx),
+				"asinh" => x.asinh(ctx),
+				"acosh" => x.acosh(ctx),
+				_ => x.atanh(ctx),
+			}
+		}
+		"atan2" => {
+			expect_args(name, args, 2)?;
+			args[0].atan2(&args[1], ctx)
+		}
+		"factorial" | "double_factorial" | "catalan" => {
+			expect_args(name, args, 1)?;
+			match name {
+				"factorial" => args[0].factorial(),
+				"double_factorial" => args[0].double_factorial(),
+				_ => args[0].catalan(),
+			}
+		}
+		"nCr" | "nPr" | "stirling1" | "stirling2" => {
+			expect_args(name, args, 2)?;
+			let (n, k) = (&args[0], &args[1]);
+			match name {
+				"nCr" => n.binomial(k),
+				"nPr" => n.permutations(k),
+				"stirling1" => n.stirling1(k),
+				_ => n.stirling2(k),
+			}
+		}
+		"multinomial" => BigNumber::multinomial(args),
+		"gcd" => BigNumber::gcd(args),
+		"lcm" => BigNumber::lcm(args),
+		"crt" => BigNumber::crt(args),
+		"modpow" => {
+			expect_args(name, args, 3)?;
+			args[0].modpow(&args[1], &args[2])
+		}
+		"modinv" => {
+			expect_args(name, args, 2)?;
+			args[0].modinv(&args[1])
+		}
+		"totient" => {
+			expect_args(name, args, 1)?;
+			args[0].totient(ctx)
+		}
+		"rol" => {
+			expect_args(name, args, 2)?;
+			args[0].rotate_left(&args[1], ctx)
+		}
+		"ror" => {
+			expect_args(name, args, 2)?;
+			args[0].rotate_right(&args[1], ctx)
+		}
+		"isprime" => {
+			expect_args(name, args, 1)?;
+			args[0].is_prime()
+		}
+		"nextprime" => {
+			expect_args(name, args, 1)?;
+			args[0].next_prime(ctx)
+		}
+		"prevprime" => {
+			expect_args(name, args, 1)?;
+			args[0].prev_prime(ctx)
+		}
+		_ => Err(format!("Unknown function '{}'", name)),
+	}
+}
+
+fn is_integer_function(name: &str) -> bool {
+	matches!(
+		name,
+		"factorial" | "double_factorial" | "catalan" | "nCr" | "nPr" | "stirling1" | "stirling2" | "multinomial"
+			| "gcd" | "lcm" | "crt" | "modpow" | "modinv" | "totient" | "isprime" | "nextprime" | "prevprime"
+			| "rol" | "ror"
+	)
+}
+
+// Functions that take values as they are instead of rounding them to decimals: the parts
+// of a complex number, the exact remainders and the factorization. Returns None for any other name.
+fn call_value_function(name: &str, args: &[Value], ctx: &Context) -> Option<Result<Value, String>> {
+	let result = match name {
+		"re" | "im" | "abs" | "arg" | "conj" => {
+			expect_args(name, args, 1).and_then(|()| complex_part(name, &args[0], ctx))
+		}
+		"rem" => expect_args(name, args, 2).and_then(|()| args[0].remainder(&args[1], ctx)),
+		"divmod" => expect_args(name, args, 2).and_then(|()| args[0].divmod(&args[1], ctx)),
+		"factor" => expect_args(name, args, 1)
+			.and_then(|()| args[0].to_decimal(ctx)?.factor(ctx))
+			.map(Value::Factored),
+		_ => return None,
+	};
+	Some(result)
+}
+
+fn complex_part(name: &str, value: &Value, ctx: &Context) -> Result<Value, String> {
+	let z = match value {
+		// The absolute value of a fraction is still exact
+		Value::Exact(value) if name == "abs" => return Ok(Value::Exact(value.abs())),
+		Value::Exact(_) | Value::Decimal(_) if name == "re" || name == "conj" => return Ok(value.clone()),
+		value => value.to_complex(ctx)?,
+	};
+	match name {
+		"re" => Ok(Value::Decimal(z.re().clone())),
+		"im" => Ok(Value::Decimal(z.im().clone())),
+		"abs" => z.abs(ctx).map(Value::Decimal),
+		"arg" => z.arg(ctx).map(Value::Decimal),
+		_ => Ok(Value::from_complex(z.conjugate())),
+	}
+}
+
+// Functions with a complex version are evaluated in the complex plane when an argument is
+// complex, or when the real function would fail because the result is complex
+fn is_complex_call(name: &str, args: &[Value]) -> bool {
+	match name {
+		"sqrt" | "ln" => args.iter().any(|arg| arg.is_complex() || arg.is_negative()),
+		"exp" | "sin" | "cos" | "tan" => args.iter().any(Value::is_complex),
+		_ => false,
+	}
+}
+
+// Complex arguments to trig functions are always in radians
+fn call_complex(name: &str, args: &[Value], ctx: &Context) -> Result<Complex, String> {
+	expect_args(name, args, 1)?;
+	let z = args[0].to_complex(ctx)?;
+	match name {
+		"sqrt" => z.sqrt(ctx),
+		"ln" => z.ln(ctx),
+		"exp" => z.exp(ctx),
+		"sin" => z.sin(ctx),
+		"cos" => z.cos(ctx),
+		_ => z.tan(ctx),
+	}
+}
+
+fn constant(name: &str, ctx: &Context) -> Result<Value, String> {
+	let value = match name {
+		"pi" | "π" => BigNumber::pi(ctx),
+		"e" => BigNumber::e(ctx),
+		"phi" | "φ" => BigNumber::phi(ctx),
+		"euler_gamma" | "γ" => BigNumber::euler_gamma(ctx),
+		"i" => return Ok(Value::Complex(Complex::i())),
+		_ => return Err(format!("Unknown constant '{}'", name)),
+	};
+	Ok(Value::Decimal(value))
+}
+
+fn expect_args<T>(name: &str, args: &[T], count: usize) -> Result<(), String> {
+	if args.len() != count {
+		let plural = if count == 1 { "" } else { "s" };
+		return Err(format!("{}() takes {} argument{}, got {}", name, count, plural, args.len()));
+	}
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::calculate;
+	use crate::context::Context;
+	use crate::number::RoundingMode;
+
+	#[test]
+	fn negated_fraction_literals_round_as_a_whole() {
+		let ctx = Context { exact: false, rounding: RoundingMode::Floor, ..Context::default() };
+		assert_eq!(calculate("-1 1/3", &ctx), "-1.333333333333334");
+		assert_eq!(calculate("-2/3", &ctx), calculate("-2 / 3", &ctx));
+	}
+
+	#[test]
+	fn exact_arithmetic_stays_exact() {
+		let ctx = Context::default();
+		assert_eq!(calculate("1/3 + 1/6", &ctx), "0.5");
+		assert_eq!(calculate("2/4 * 6", &ctx), "3");
+	}
+
+	#[test]
+	fn huge_literal_exponents_fail_instead_of_spelling_out_the_fraction() {
+		let ctx = Context::default();
+		assert_eq!(calculate("1e999999999", &ctx), "Error: Result too large: about 999999999 digits (limit is 1000000)");
+		assert!(calculate("1e-999999999", &ctx).starts_with("Error: Result too large"));
+		assert_eq!(calculate("1e999999999", &Context { exact: false, ..ctx }), "1e999999999");
+	}
+
+	#[test]
+	fn modulo_follows_the_divisor_and_rem_the_dividend() {
+		let ctx = Context::default();
+		assert_eq!(calculate("-7 mod 3", &ctx), "2");
+		assert_eq!(calculate("rem(-7, 3)", &ctx), "-1");
+		assert_eq!(calculate("-7 // 2", &ctx), "-4");
+	}
+}
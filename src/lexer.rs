@@ -0,0 +1,338 @@
+// Tokenizer for calculator expressions
+
+use crate::context::MAX_RADIX;
+use crate::number::radix_prefix;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+	Number(String),
+	Dms(String, Option<String>, Option<String>), // Degrees, minutes and seconds of an angle
+	Fraction(String, String, String), // Mixed number: whole part, numerator and denominator
+	Imaginary(String), // Number with an i suffix, like 2i
+	Radix(u32, String), // Base and digits of a number like 0xFF or 36#ZZ
+	Ident(String),
+	Plus,
+	Minus,
+	Star,
+	Slash,
+	DoubleSlash,
+	Percent,
+	Mod,
+	Caret,
+	LParen,
+	RParen,
+	Comma,
+	Bang,
+	DoubleBang,
+	Sqrt,
+	Polar,
+	BitAnd,
+	BitOr,
+	Xor,
+	BitNot,
+	ShiftLeft,
+	ShiftRight,
+}
+
+impl Token {
+	pub fn describe(&self) -> String {
+		match self {
+			Token::Number(s) => format!("number '{}'", s),
+			Token::Dms(degrees, minutes, seconds) => {
+				let minutes = minutes.as_ref().map(|m| format!("{}'", m)).unwrap_or_default();
+				let seconds = seconds.as_ref().map(|s| format!("{}\"", s)).unwrap_or_default();
+				format!("angle '{}°{}{}'", degrees, minutes, seconds)
+			}
+			Token::Fraction(whole, numerator, denominator) => format!("fraction '{} {}/{}'", whole, numerator, denominator),
+			Token::Imaginary(s) => format!("number '{}i'", s),
+			Token::Radix(radix, digits) => format!("number '{}{}'", radix_prefix(*radix), digits),
+			Token::Ident(name) => format!("'{}'", name),
+			Token::Plus => "'+'".to_string(),
+			Token::Minus => "'-'".to_string(),
+			Token::Star => "'*'".to_string(),
+			Token::Slash => "'/'".to_string(),
+			Token::DoubleSlash => "'//'".to_string(),
+			Token::Percent => "'%'".to_string(),
+			Token::Mod => "'mod'".to_string(),
+			Token::Caret => "'^'".to_string(),
+			Token::LParen => "'('".to_string(),
+			Token::RParen => "')'".to_string(),
+			Token::Comma => "','".to_string(),
+			Token::Bang => "'!'".to_string(),
+			Token::DoubleBang => "'!!'".to_string(),
+			Token::Sqrt => "'√'".to_string(),
+			Token::Polar => "'∠'".to_string(),
+			Token::BitAnd => "'&'".to_string(),
+			Token::BitOr => "'|'".to_string(),
+			Token::Xor => "'xor'".to_string(),
+			Token::BitNot => "'~'".to_string(),
+			Token::ShiftLeft => "'<<'".to_string(),
+			Token::ShiftRight => "'>>'".to_string(),
+		}
+	}
+}
+
+pub fn tokenize(input: &str) -> Result<Vec<Token>, String> {
+	let chars: Vec<char> = input.chars().collect();
+	let mut tokens = Vec::new();
+	let mut pos = 0;
+
+	while pos < chars.len() {
+		let c = chars[pos];
+
+		if c.is_whitespace() {
+			pos += 1;
+			continue;
+		}
+
+		// 0x, 0o and 0b start hexadecimal, octal and binary numbers
+		if c == '0' && let Some(radix) = chars.get(pos + 1).and_then(|&c| prefix_radix(c)) {
+			let (digits, next) = read_radix_digits(&chars, pos + 2);
+			if digits.is_empty() {
+				return Err(format!("Missing digits after '0{}' at position {}", chars[pos + 1], pos + 1));
+			}
+			tokens.push(Token::Radix(radix, digits));
+			pos = next;
+			continue;
+		}
+
+		if c.is_ascii_digit() || c == '.' {
+			let (literal, next) = read_number(&chars, pos);
+			// Any other base goes before a #, as in 36#ZZ
+			if chars.get(next) == Some(&'#') && literal.chars().all(|c| c.is_ascii_digit()) {
+				let radix = literal.parse::<u32>().ok().filter(|radix| (2..=MAX_RADIX).contains(radix))
+					.ok_or_else(|| format!("Base must be from 2 to {}, got {}", MAX_RADIX, literal))?;
+				let (digits, next) = read_radix_digits(&chars, next + 1);
+				if digits.is_empty() {
+					return Err(format!("Missing digits after '{}#' at position {}", literal, pos + 1));
+				}
+				tokens.push(Token::Radix(radix, digits));
+				pos = next;
+				continue;
+			}
+			if chars.get(next) == Some(&'°') {
+				let (token, next) = read_dms(&chars, literal, next + 1)?;
+				tokens.push(token);
+				pos = next;
+			} else if let Some((token, next)) = read_fraction(&chars, &literal, next) {
+				tokens.push(token);
+				pos = next;
+			} else if chars.get(next) == Some(&'i') && !chars.get(next + 1).is_some_and(|c| c.is_alphanumeric() || *c == '_') {
+				tokens.push(Token::Imaginary(literal));
+				pos = next + 1;
+			} else {
+				tokens.push(Token::Number(literal));
+				pos = next;
+			}
+			continue;
+		}
+
+		if c.is_alphabetic() {
+			let start = pos;
+			while pos < chars.len() && (chars[pos].is_alphanumeric() || chars[pos] == '_') {
+				pos += 1;
+			}
+			let name: String = chars[start..pos].iter().collect();
+			// Operators spelled as words, not function or constant names
+			let token = match name.as_str() {
+				"mod" => Token::Mod,
+				"and" => Token::BitAnd,
+				"or" => Token::BitOr,
+				"xor" => Token::Xor,
+				"not" => Token::BitNot,
+				_ => Token::Ident(name),
+			};
+			tokens.push(token);
+			continue;
+		}
+
+		if c == '/' && chars.get(pos + 1) == Some(&'/') {
+			tokens.push(Token::DoubleSlash);
+			pos += 2;
+			continue;
+		}
+
+		if c == '<' && chars.get(pos + 1) == Some(&'<') {
+			tokens.push(Token::ShiftLeft);
+			pos += 2;
+			continue;
+		}
+
+		if c == '>' && chars.get(pos + 1) == Some(&'>') {
+			tokens.push(Token::ShiftRight);
+			pos += 2;
+			continue;
+		}
+
+		// n!! is the double factorial; write (n!)! for a factorial of a factorial
+		if c == '!' && chars.get(pos + 1) == Some(&'!') {
+			tokens.push(Token::DoubleBang);
+			pos += 2;
+			continue;
+		}
+
+		let token = match c {
+			'+' => Token::Plus,
+			'-' | '−' => Token::Minus,
+			'*' | '×' => Token::Star,
+			'/' | '÷' => Token::Slash,
+			'%' => Token::Percent,
+			'^' => Token::Caret,
+			'(' => Token::LParen,
+			')' => Token::RParen,
+			',' => Token::Comma,
+			'!' => Token::Bang,
+			'√' => Token::Sqrt,
+			'∠' => Token::Polar,
+			'&' => Token::BitAnd,
+			'|' => Token::BitOr,
+			'~' => Token::BitNot,
+			_ => return Err(format!("Unexpected character '{}' at position {}", c, pos + 1)),
+		};
+		tokens.push(token);
+		pos += 1;
+	}
+
+	Ok(tokens)
+}
+
+// Reads digits, an optional fraction and an optional exponent ("1.5e-3").
+// The exponent marker is only consumed when digits actually follow it.
+// Digits in parentheses right after the fraction repeat forever ("0.1(6)").
+fn read_number(chars: &[char], start: usize) -> (String, usize) {
+	let mut pos = start;
+	while pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+		pos += 1;
+	}
+	
+	if chars[start..pos].contains(&'.') && chars.get(pos) == Some(&'(') {
+		let mut end = pos + 1;
+		while end < chars.len() && chars[end].is_ascii_digit() {
+			end += 1;
+		}
+		if end > pos + 1 && chars.get(end) == Some(&')') {
+			return (chars[start..=end].iter().collect(), end + 1);
+		}
+	}
+
+	if pos < chars.len() && (chars[pos] == 'e' || chars[pos] == 'E') {
+		let mut exp_end = pos + 1;
+		if exp_end < chars.len() && (chars[exp_end] == '+' || chars[exp_end] == '-') {
+			exp_end += 1;
+		}
+		if exp_end < chars.len() && chars[exp_end].is_ascii_digit() {
+			while exp_end < chars.len() && chars[exp_end].is_ascii_digit() {
+				exp_end += 1;
+			}
+			pos = exp_end;
+		}
+	}
+
+	(chars[start..pos].iter().collect(), pos)
+}
+
+fn prefix_radix(c: char) -> Option<u32> {
+	match c {
+		'x' | 'X' => Some(16),
+		'o' | 'O' => Some(8),
+		'b' | 'B' => Some(2),
+		_ => None,
+	}
+}
+
+// Reads the digits of a number in another base, which may include letters, a point and
+// digits in parentheses that repeat like in read_number. Whether the digits are valid in
+// that base is checked when the number is parsed.
+fn read_radix_digits(chars: &[char], start: usize) -> (String, usize) {
+	let mut pos = start;
+	while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '.') {
+		pos += 1;
+	}
+
+	if chars[start..pos].contains(&'.') && chars.get(pos) == Some(&'(') {
+		let mut end = pos + 1;
+		while end < chars.len() && chars[end].is_ascii_alphanumeric() {
+			end += 1;
+		}
+		if end > pos + 1 && chars.get(end) == Some(&')') {
+			return (chars[start..=end].iter().collect(), end + 1);
+		}
+	}
+
+	(chars[start..pos].iter().collect(), pos)
+}
+
+// Reads the optional minutes and seconds of an angle like 12°30'15", starting after the
+// degree sign. Both accept the typographic primes as well as ' and ".
+fn read_dms(chars: &[char], degrees: String, start: usize) -> Result<(Token, usize), String> {
+	let mut minutes = None;
+	let mut seconds = None;
+	let mut pos = start;
+
+	while seconds.is_none() && pos < chars.len() && (chars[pos].is_ascii_digit() || chars[pos] == '.') {
+		let (literal, next) = read_number(chars, pos);
+		match chars.get(next) {
+			Some('\'' | '′') if minutes.is_none() => minutes = Some(literal),
+			Some('"' | '″') => seconds = Some(literal),
+			_ => return Err(format!("Expected ' or \" after '{}' in angle at position {}", literal, pos + 1)),
+		}
+		pos = next + 1;
+	}
+
+	Ok((Token::Dms(degrees, minutes, seconds), pos))
+}
+
+// Reads a mixed number like "1 3/4" following the integer `first`. A plain "3/4" is an
+// ordinary division, which keeps its precedence however it is spaced and is exact anyway.
+fn read_fraction(chars: &[char], first: &str, start: usize) -> Option<(Token, usize)> {
+	if !first.chars().all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+
+	let mut pos = start;
+	while pos < chars.len() && chars[pos] == ' ' {
+		pos += 1;
+	}
+	if pos == start {
+		return None;
+	}
+	let (numerator, next) = read_integer(chars, pos)?;
+	if chars.get(next) != Some(&'/') {
+		return None;
+	}
+	let (denominator, next) = read_integer(chars, next + 1)?;
+	Some((Token::Fraction(first.to_string(), numerator, denominator), next))
+}
+
+// A run of digits that is a whole number literal, not the start of a decimal or exponent
+fn read_integer(chars: &[char], start: usize) -> Option<(String, usize)> {
+	let (literal, next) = read_number(chars, start);
+	if literal.is_empty() || !literal.chars().all(|c| c.is_ascii_digit()) {
+		return None;
+	}
+	Some((literal, next))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn plain_fractions_are_divisions() {
+		let tokens = tokenize("3/4").unwrap();
+		assert_eq!(tokens, vec![Token::Number("3".to_string()), Token::Slash, Token::Number("4".to_string())]);
+	}
+
+	#[test]
+	fn mixed_numbers_are_one_token() {
+		let tokens = tokenize("1 3/4").unwrap();
+		assert_eq!(tokens, vec![Token::Fraction("1".to_string(), "3".to_string(), "4".to_string())]);
+	}
+
+	#[test]
+	fn radix_prefixes_and_bases() {
+		assert_eq!(tokenize("0xFF").unwrap(), vec![Token::Radix(16, "FF".to_string())]);
+		assert_eq!(tokenize("36#ZZ").unwrap(), vec![Token::Radix(36, "ZZ".to_string())]);
+		assert!(tokenize("37#1").is_err());
+	}
+}
@@ -1,9 +1,23 @@
 use iced::{
-	widget::{button, column, container, row, scrollable, text, text_input, Space},
-	Element, Length, Sandbox, Settings, Theme, Size, Alignment, alignment::{Vertical, Horizontal},
+	widget::{button, checkbox, column, container, pick_list, row, scrollable, text, text_input, tooltip, Space},
+	Application, Command, Element, Length, Settings, Theme, Size, Alignment, alignment::{Vertical, Horizontal}, executor,
 };
+mod context;
+mod emulate;
+mod eval;
+mod lexer;
 mod number;
-use crate::number::{BigNumber, parse_number};
+mod parser;
+mod value;
+use num_bigint::BigInt;
+use num_traits::Zero;
+use crate::context::{AngleUnit, Cancellation, Context, FractionStyle, WordSize, MAX_DISPLAY_LIMIT, MAX_PRECISION, MAX_RADIX};
+use crate::emulate::{evaluate_f64, inexact_literals};
+use crate::eval::evaluate;
+use crate::lexer::tokenize;
+use crate::number::{Float, FloatFormat, Rational, RoundingMode, WordFlags};
+use crate::parser::parse;
+use crate::value::Value;
 
 #[derive(Debug, Clone)]
 pub struct HistoryEntry {
@@ -18,62 +32,130 @@ pub struct Calculator {
 	previous_result: Option<String>,
 	history: Vec<HistoryEntry>,
 	show_history: bool,
+	show_scientific: bool,
+	second_page: bool, // Scientific keys show the hyperbolic functions
+	show_approximations: bool,
+	show_programmer: bool, // Integer results are wrapped to the word size
+	word_flags: Option<WordFlags>, // Carry and overflow of the last result in programmer mode
+	show_float: bool,
+	float_format: FloatFormat, // Format the float panel rounds the last result to
+	float_literals: Vec<(Rational, Float)>, // Literals of the last emulated input that f64 rounded
+	denominator_bound_input: String,
+	last_value: Option<Value>, // Most recent result, analysed by the approximation panel
 	history_index: usize, // For navigation through history
+	context: Context,
+	precision_input: String,
+	display_limit_input: String,
+	running: Option<Cancellation>, // Stops the calculation in progress, if any
 }
 
 #[derive(Debug, Clone)]
 pub enum Message {
 	InputChanged(String),
 	Calculate,
+	Calculated(Cancellation, String, Result<Value, String>), // Identifies the calculation, its input and result
+	ConvertedToDms(Cancellation, String, Result<String, String>),
+	CancelCalculation,
+	ConvertToDms,
 	Clear,
 	Backspace,
 	AddDigit(char),
 	AddOperator(char),
+	AddParenthesis(char),
+	InsertText(&'static str),
 	AddDecimal,
 	AddScientificE,
 	ToggleSign,
 	ToggleHistory,
+	ToggleScientific,
+	ToggleSecondPage,
+	ToggleApproximations,
+	ToggleProgrammer,
+	WordSizeSelected(WordSize),
+	SignedToggled(bool),
+	ToggleBit(u32),
+	ToggleFloat,
+	FloatFormatSelected(FloatFormat),
+	EmulateToggled(bool),
+	DenominatorBoundChanged(String),
 	ClearHistory,
 	NavigateHistoryPrevious,
 	NavigateHistoryNext,
+	RoundingModeSelected(RoundingMode),
+	AngleUnitSelected(AngleUnit),
+	ExactToggled(bool),
+	FractionStyleSelected(FractionStyle),
+	PolarToggled(bool),
+	RadixSelected(u32),
+	PrecisionChanged(String),
+	DisplayLimitChanged(String),
 }
 
-impl Sandbox for Calculator {
+impl Application for Calculator {
+	type Executor = executor::Default;
 	type Message = Message;
+	type Theme = Theme;
+	type Flags = ();
 
-	fn new() -> Self {
-		Calculator {
-			current_input: "0".to_string(),
-			previous_result: None,
-			history: Vec::new(),
-			show_history: false,
-			history_index: 0,
-		}
+	fn new(_flags: ()) -> (Self, Command<Message>) {
+		(Calculator::with_context(Context::load()), Command::none())
 	}
 
 	fn title(&self) -> String {
 		String::from("Big Number Calculator Pro")
 	}
 
-	fn update(&mut self, message: Message) {
+	fn update(&mut self, message: Message) -> Command<Message> {
 		match message {
 			Message::InputChanged(input) => {
 				self.current_input = input;
 			}
+			// Calculations run in the background, since factoring or searching for primes
+			// can take a long time, and a new one replaces any still running
 			Message::Calculate => {
-				let input = self.current_input.clone();
-				match evaluate_expression(&input) {
-					Ok(result) => {
-						self.add_to_history(input, result.clone(), false);
-						self.previous_result = Some(result.clone());
-						self.current_input = result;
-						self.history_index = 0; // Reset to most recent
-					}
-					Err(e) => {
-						self.add_to_history(input, e.clone(), true);
-						self.current_input = format!("Error: {}", e);
-						self.history_index = 0; // Reset to most recent
-					}
+				let (input, cancellation, ctx) = self.start_calculation();
+				return Command::perform(
+					async move {
+						let result = evaluate_input(&input, &ctx);
+						(input, result)
+					},
+					move |(input, result)| Message::Calculated(cancellation.clone(), input, result),
+				);
+			}
+			Message::Calculated(cancellation, input, result) => {
+				if !self.finish_calculation(&cancellation) {
+					return Command::none();
+				}
+				self.float_literals = if self.context.emulate_f64 {
+					tokenize(&input).and_then(parse).map(|ast| inexact_literals(&ast)).unwrap_or_default()
+				} else {
+					Vec::new()
+				};
+				let result = result.and_then(|value| self.wrap_to_word(value));
+				self.last_value = result.as_ref().ok().cloned();
+				let displayed = result.map(|value| value.to_display_string(&self.context));
+				self.show_result(input, displayed);
+			}
+			Message::CancelCalculation => {
+				if let Some(running) = self.running.take() {
+					running.cancel();
+					self.show_result(self.current_input.clone(), Err("Cancelled".to_string()));
+				}
+			}
+			Message::ConvertToDms => {
+				let (input, cancellation, ctx) = self.start_calculation();
+				return Command::perform(
+					async move {
+						let result = evaluate_input(&input, &ctx)
+							.and_then(|value| value.to_decimal(&ctx)?.to_dms_string(&ctx));
+						(input, result)
+					},
+					move |(input, result)| Message::ConvertedToDms(cancellation.clone(), input, result),
+				);
+			}
+			Message::ConvertedToDms(cancellation, input, result) => {
+				if self.finish_calculation(&cancellation) {
+					self.show_result(input, result);
 				}
 			}
 			Message::Clear => {
@@ -82,8 +164,9 @@ impl Sandbox for Calculator {
 				self.history_index = 0;
 			}
 			Message::Backspace => {
-				if self.current_input.len() > 1 && !self.current_input.starts_with("Error:") {
-					self.current_input = self.current_input[..self.current_input.len() - 1].to_string();
+				// Removes a whole character, since keys like √ and π insert multi-byte ones
+				if self.current_input.chars().count() > 1 && !self.current_input.starts_with("Error:") {
+					self.current_input.pop();
 				} else {
 					self.current_input = "0".to_string();
 				}
@@ -101,7 +184,7 @@ impl Sandbox for Calculator {
 				} else {
 					// Check if the last character is already an operator
 					let trimmed = self.current_input.trim_end();
-					if trimmed.ends_with(&['+', '-', '*', '/', '^']) {
+					if trimmed.ends_with(['+', '-', '*', '/', '^']) {
 						// Replace the last operator
 						let mut chars: Vec<char> = trimmed.chars().collect();
 						while let Some(&last) = chars.last() {
@@ -118,19 +201,32 @@ impl Sandbox for Calculator {
 					}
 				}
 			}
+			Message::AddParenthesis(paren) => {
+				if self.current_input.starts_with("Error:") || self.current_input == "0" {
+					self.current_input = paren.to_string();
+				} else {
+					self.current_input.push(paren);
+				}
+			}
+			Message::InsertText(snippet) => {
+				if self.current_input.starts_with("Error:") || self.current_input == "0" {
+					self.current_input = snippet.to_string();
+				} else {
+					self.current_input.push_str(snippet);
+				}
+			}
 			Message::AddDecimal => {
 				if self.current_input.starts_with("Error:") {
 					self.current_input = "0.".to_string();
 				} else {
 					// Check if current number already has a decimal point
 					let parts: Vec<&str> = self.current_input.split_whitespace().collect();
-					if let Some(last_part) = parts.last() {
-						if !last_part.contains('.') && !last_part.contains('e') && !last_part.contains('E') {
-							if last_part.chars().all(|c| "+-*/^".contains(c)) {
-								self.current_input = format!("{}0.", self.current_input);
-							} else {
-								self.current_input.push('.');
-							}
+					if let Some(last_part) = parts.last()
+						&& !last_part.contains('.') && !last_part.contains('e') && !last_part.contains('E') {
+						if last_part.chars().all(|c| "+-*/^".contains(c)) {
+							self.current_input = format!("{}0.", self.current_input);
+						} else {
+							self.current_input.push('.');
 						}
 					}
 				}
@@ -141,20 +237,19 @@ impl Sandbox for Calculator {
 				} else {
 					// Add 'e' for scientific notation
 					let parts: Vec<&str> = self.current_input.split_whitespace().collect();
-					if let Some(last_part) = parts.last() {
-						if !last_part.contains('e') && !last_part.contains('E') {
-							if last_part.chars().all(|c| "+-*/^".contains(c)) {
-								self.current_input = format!("{}1e", self.current_input);
-							} else {
-								self.current_input.push('e');
-							}
+					if let Some(last_part) = parts.last()
+						&& !last_part.contains('e') && !last_part.contains('E') {
+						if last_part.chars().all(|c| "+-*/^".contains(c)) {
+							self.current_input = format!("{}1e", self.current_input);
+						} else {
+							self.current_input.push('e');
 						}
 					}
 				}
 			}
 			Message::ToggleSign => {
 				if self.current_input.starts_with("Error:") {
-					return;
+					return Command::none();
 				}
 				
 				let parts: Vec<&str> = self.current_input.split_whitespace().collect();
@@ -170,6 +265,51 @@ impl Sandbox for Calculator {
 			Message::ToggleHistory => {
 				self.show_history = !self.show_history;
 			}
+			Message::ToggleScientific => {
+				self.show_scientific = !self.show_scientific;
+			}
+			Message::ToggleSecondPage => {
+				self.second_page = !self.second_page;
+			}
+			Message::ToggleApproximations => {
+				self.show_approximations = !self.show_approximations;
+			}
+			Message::ToggleProgrammer => {
+				self.show_programmer = !self.show_programmer;
+			}
+			Message::WordSizeSelected(size) => {
+				self.context.word_size = size;
+				self.save_context();
+			}
+			Message::SignedToggled(signed) => {
+				self.context.signed = signed;
+				self.save_context();
+			}
+			// Flipping a bit changes the current value in place, without a history entry
+			Message::ToggleBit(bit) => {
+				if let Some(value) = &self.last_value
+					&& let Ok(value) = value.to_decimal(&self.context)
+						.and_then(|word| word.toggle_bit(bit, &self.context))
+						.and_then(|toggled| Value::from_literal(&toggled, &self.context)) {
+					self.current_input = value.to_display_string(&self.context);
+					self.previous_result = Some(self.current_input.clone());
+					self.last_value = Some(value);
+					self.word_flags = Some(WordFlags::default());
+				}
+			}
+			Message::ToggleFloat => {
+				self.show_float = !self.show_float;
+			}
+			Message::FloatFormatSelected(format) => {
+				self.float_format = format;
+			}
+			Message::EmulateToggled(emulate) => {
+				self.context.emulate_f64 = emulate;
+				self.save_context();
+			}
+			Message::DenominatorBoundChanged(input) => {
+				self.denominator_bound_input = input;
+			}
 			Message::ClearHistory => {
 				self.history.clear();
 				self.history_index = 0;
@@ -192,10 +332,51 @@ impl Sandbox for Calculator {
 					}
 				}
 			}
+			Message::RoundingModeSelected(mode) => {
+				self.context.rounding = mode;
+				self.save_context();
+			}
+			Message::AngleUnitSelected(unit) => {
+				self.context.angle_unit = unit;
+				self.save_context();
+			}
+			Message::ExactToggled(exact) => {
+				self.context.exact = exact;
+				self.save_context();
+			}
+			Message::FractionStyleSelected(style) => {
+				self.context.fraction_style = style;
+				self.save_context();
+			}
+			Message::PolarToggled(polar) => {
+				self.context.polar = polar;
+				self.save_context();
+			}
+			Message::RadixSelected(radix) => {
+				self.context.radix = radix;
+				self.save_context();
+			}
+			Message::PrecisionChanged(input) => {
+				if let Ok(precision) = input.trim().parse::<i32>()
+					&& (0..=MAX_PRECISION).contains(&precision) {
+					self.context.precision = precision;
+					self.save_context();
+				}
+				self.precision_input = input;
+			}
+			Message::DisplayLimitChanged(input) => {
+				if let Ok(limit) = input.trim().parse::<usize>()
+					&& (1..=MAX_DISPLAY_LIMIT).contains(&limit) {
+					self.context.display_limit = limit;
+					self.save_context();
+				}
+				self.display_limit_input = input;
+			}
 		}
+		Command::none()
 	}
 
-	fn view(&self) -> Element<Message> {
+	fn view(&self) -> Element<'_, Message> {
 		let display_text = &self.current_input;
 
 		let display = text_input("0", display_text)
@@ -204,23 +385,31 @@ impl Sandbox for Calculator {
 			.padding(5)
 			.width(Length::Fill);
 
+		let settings = self.create_settings_row();
+
 		let calculator_buttons = self.create_button_grid();
 
-		let calculator_panel = container(column![display, calculator_buttons].spacing(25))
+		let calculator_panel = container(column![display, settings, calculator_buttons].spacing(15))
 			.padding(25)
 			.width(Length::Fixed(320.0));
 
+		let mut panels = row![calculator_panel].spacing(10);
+		if self.show_scientific {
+			panels = panels.push(self.create_scientific_panel());
+		}
+		if self.show_programmer {
+			panels = panels.push(self.create_programmer_panel());
+		}
+		if self.show_float {
+			panels = panels.push(self.create_float_panel());
+		}
+		if self.show_approximations {
+			panels = panels.push(self.create_approximation_panel());
+		}
 		if self.show_history {
-			let history_panel = self.create_history_panel();
-			container(
-				row![
-					calculator_panel,
-					history_panel
-				].spacing(10)
-			).into()
-		} else {
-			calculator_panel.into()
+			panels = panels.push(self.create_history_panel());
 		}
+		container(panels).into()
 	}
 
 	fn theme(&self) -> Theme {
@@ -229,6 +418,74 @@ impl Sandbox for Calculator {
 }
 
 impl Calculator {
+	// A fresh calculator working in the given settings
+	fn with_context(context: Context) -> Self {
+		Calculator {
+			current_input: "0".to_string(),
+			previous_result: None,
+			history: Vec::new(),
+			show_history: false,
+			show_scientific: false,
+			second_page: false,
+			show_approximations: false,
+			show_programmer: false,
+			word_flags: None,
+			show_float: false,
+			float_format: FloatFormat::default(),
+			float_literals: Vec::new(),
+			denominator_bound_input: "1000".to_string(),
+			last_value: None,
+			history_index: 0,
+			precision_input: context.precision.to_string(),
+			display_limit_input: context.display_limit.to_string(),
+			running: None,
+			context,
+		}
+	}
+
+	// Cancels any calculation still running and sets up a new one on the current input,
+	// with the context it evaluates in
+	fn start_calculation(&mut self) -> (String, Cancellation, Context) {
+		if let Some(running) = self.running.take() {
+			running.cancel();
+		}
+		let cancellation = Cancellation::default();
+		let mut ctx = self.context.clone();
+		ctx.cancellation = cancellation.clone();
+		// Bitwise operators only wrap to the word in programmer mode, so that
+		// elsewhere 2^70 | 0 stays 2^70
+		if !self.show_programmer {
+			ctx.word_size = WordSize::Unbounded;
+		}
+		self.running = Some(cancellation.clone());
+		(self.current_input.clone(), cancellation, ctx)
+	}
+	
+	// Whether a finished calculation is the one still expected; results of cancelled or
+	// replaced calculations are dropped
+	fn finish_calculation(&mut self, cancellation: &Cancellation) -> bool {
+		if self.running.as_ref() != Some(cancellation) {
+			return false;
+		}
+		self.running = None;
+		true
+	}
+	
+	fn show_result(&mut self, input: String, result: Result<String, String>) {
+		match result {
+			Ok(result) => {
+				self.add_to_history(input, result.clone(), false);
+				self.previous_result = Some(result.clone());
+				self.current_input = result;
+			}
+			Err(e) => {
+				self.add_to_history(input, e.clone(), true);
+				self.current_input = format!("Error: {}", e);
+			}
+		}
+		self.history_index = 0; // Reset to most recent
+	}
+	
 	fn add_to_history(&mut self, input: String, output: String, is_error: bool) {
 		self.history.push(HistoryEntry {
 			input,
@@ -242,7 +499,7 @@ impl Calculator {
 		}
 	}
 	
-	fn create_button_grid(&self) -> Element<Message> {
+	fn create_button_grid(&self) -> Element<'_, Message> {
 		let spacing = 6;
 		column![
 			// First row: Clear functions, history, and division
@@ -278,18 +535,218 @@ impl Calculator {
 				self.create_button("0", Message::AddDigit('0')),
 				self.create_button(".", Message::AddDecimal),
 				self.create_button("^", Message::AddOperator('^')),
-				self.create_button("=", Message::Calculate),
+				// Stops a long calculation while it runs
+				match self.running {
+					Some(_) => self.create_button("■", Message::CancelCalculation),
+					None => self.create_button("=", Message::Calculate),
+				},
 			].spacing(spacing).align_items(Alignment::Center),
-			// Sixth row: sign, backspace, scientific notation
+			// Sixth row: sign, scientific notation, grouping
 			row![
 				self.create_button("±", Message::ToggleSign),
 				self.create_button("e", Message::AddScientificE),
+				self.create_button("(", Message::AddParenthesis('(')),
+				self.create_button(")", Message::AddParenthesis(')')),
+			].spacing(spacing).align_items(Alignment::Center),
+			// Seventh row: square root, natural logarithm and history navigation
+			row![
+				self.create_button("√", Message::InsertText("√")),
+				self.create_button("ln", Message::InsertText("ln(")),
 				self.create_button("<", Message::NavigateHistoryNext),
 				self.create_button(">", Message::NavigateHistoryPrevious),
 			].spacing(spacing).align_items(Alignment::Center),
 		].spacing(spacing).into()
 	}
 	
+	fn save_context(&self) {
+		if let Err(e) = self.context.save() {
+			eprintln!("Failed to save settings: {}", e);
+		}
+	}
+	
+	fn create_settings_row(&self) -> Element<'_, Message> {
+		column![
+			row![
+				text("Rounding").size(14),
+				Space::with_width(Length::Fill),
+				pick_list(
+					&RoundingMode::ALL[..],
+					Some(self.context.rounding),
+					Message::RoundingModeSelected
+				)
+				.text_size(14)
+				.width(Length::Fixed(105.0)),
+				Space::with_width(6),
+				button(text("Sci").size(14)).on_press(Message::ToggleScientific),
+				Space::with_width(4),
+				button(text("≈").size(14)).on_press(Message::ToggleApproximations),
+			].align_items(Alignment::Center),
+			row![
+				text("Digits").size(14),
+				Space::with_width(Length::Fill),
+				text_input("15", &self.precision_input)
+					.on_input(Message::PrecisionChanged)
+					.size(14)
+					.width(Length::Fixed(55.0)),
+				Space::with_width(10),
+				text("Width").size(14),
+				Space::with_width(Length::Fill),
+				text_input("25", &self.display_limit_input)
+					.on_input(Message::DisplayLimitChanged)
+					.size(14)
+					.width(Length::Fixed(55.0)),
+			].align_items(Alignment::Center),
+			row![
+				checkbox("Exact", self.context.exact)
+					.on_toggle(Message::ExactToggled)
+					.size(14)
+					.text_size(14),
+				Space::with_width(8),
+				checkbox("Polar", self.context.polar)
+					.on_toggle(Message::PolarToggled)
+					.size(14)
+					.text_size(14),
+				Space::with_width(Length::Fill),
+				text("Show").size(14),
+				Space::with_width(8),
+				pick_list(
+					&FractionStyle::ALL[..],
+					Some(self.context.fraction_style),
+					Message::FractionStyleSelected
+				)
+				.text_size(14)
+				.width(Length::Fixed(100.0)),
+			].align_items(Alignment::Center),
+			// Base of the results; input can be in any base with 0x, 0o, 0b or 36#
+			row![
+				text("Base").size(14),
+				Space::with_width(Length::Fill),
+				pick_list(
+					(2..=MAX_RADIX).collect::<Vec<u32>>(),
+					Some(self.context.radix),
+					Message::RadixSelected
+				)
+				.text_size(14)
+				.width(Length::Fixed(70.0)),
+				Space::with_width(6),
+				button(text("Prog").size(14)).on_press(Message::ToggleProgrammer),
+				Space::with_width(4),
+				button(text("754").size(14)).on_press(Message::ToggleFloat),
+			].align_items(Alignment::Center),
+		].spacing(6).into()
+	}
+	
+	fn create_scientific_panel(&self) -> Element<'_, Message> {
+		let spacing = 6;
+		container(
+			column![
+				row![
+					text("Scientific").size(16),
+					Space::with_width(Length::Fill),
+					pick_list(
+						&AngleUnit::ALL[..],
+						Some(self.context.angle_unit),
+						Message::AngleUnitSelected
+					)
+					.text_size(14)
+					.width(Length::Fixed(110.0)),
+				].align_items(Alignment::Center),
+				self.create_function_keys(),
+				// Logarithms, general roots and the page switch
+				row![
+					self.create_button("log", Message::InsertText("log(")),
+					self.create_button("log₂", Message::InsertText("log2(")),
+					self.create_button("root", Message::InsertText("root(")),
+					self.create_button("2nd", Message::ToggleSecondPage),
+				].spacing(spacing).align_items(Alignment::Center),
+				// Mathematical constants
+				row![
+					self.create_button("π", Message::InsertText("π")),
+					self.create_button("e", Message::InsertText("e")),
+					self.create_button("φ", Message::InsertText("φ")),
+					self.create_button("γ", Message::InsertText("γ")),
+				].spacing(spacing).align_items(Alignment::Center),
+				// Factorials, combinations and remainders
+				row![
+					self.create_button("n!", Message::InsertText("!")),
+					self.create_button("nCr", Message::InsertText("nCr(")),
+					self.create_button("nPr", Message::InsertText("nPr(")),
+					self.create_button("mod", Message::InsertText(" mod ")),
+				].spacing(spacing).align_items(Alignment::Center),
+				// Primes and factorization
+				row![
+					self.create_button("prime?", Message::InsertText("isprime(")),
+					self.create_button("next", Message::InsertText("nextprime(")),
+					self.create_button("prev", Message::InsertText("prevprime(")),
+					self.create_button("factor", Message::InsertText("factor(")),
+				].spacing(spacing).align_items(Alignment::Center),
+				// Complex numbers in rectangular and polar form
+				row![
+					self.create_button("i", Message::InsertText("i")),
+					self.create_button("∠", Message::InsertText("∠")),
+					self.create_button("abs", Message::InsertText("abs(")),
+					self.create_button("arg", Message::InsertText("arg(")),
+				].spacing(spacing).align_items(Alignment::Center),
+				// Degrees-minutes-seconds input and conversion of the result
+				row![
+					self.create_button("°", Message::InsertText("°")),
+					self.create_button("′", Message::InsertText("'")),
+					self.create_button("″", Message::InsertText("\"")),
+					self.create_button("DMS", Message::ConvertToDms),
+				].spacing(spacing).align_items(Alignment::Center),
+			].spacing(spacing)
+		)
+		.padding(25)
+		.into()
+	}
+	
+	// Function keys that change with the "2nd" page switch
+	fn create_function_keys(&self) -> Element<'_, Message> {
+		let spacing = 6;
+		if self.second_page {
+			return column![
+				// Hyperbolic functions and the argument separator
+				row![
+					self.create_button("sinh", Message::InsertText("sinh(")),
+					self.create_button("cosh", Message::InsertText("cosh(")),
+					self.create_button("tanh", Message::InsertText("tanh(")),
+					self.create_button(",", Message::InsertText(", ")),
+				].spacing(spacing).align_items(Alignment::Center),
+				// Inverse hyperbolic functions and the exponential
+				row![
+					self.create_button("asinh", Message::InsertText("asinh(")),
+					self.create_button("acosh", Message::InsertText("acosh(")),
+					self.create_button("atanh", Message::InsertText("atanh(")),
+					self.create_button("exp", Message::InsertText("exp(")),
+				].spacing(spacing).align_items(Alignment::Center),
+			].spacing(spacing).into();
+		}
+		
+		column![
+			// Trigonometric functions and the argument separator
+			row![
+				self.create_button("sin", Message::InsertText("sin(")),
+				self.create_button("cos", Message::InsertText("cos(")),
+				self.create_button("tan", Message::InsertText("tan(")),
+				self.create_button(",", Message::InsertText(", ")),
+			].spacing(spacing).align_items(Alignment::Center),
+			// Inverse trigonometric functions
+			row![
+				self.create_button("asin", Message::InsertText("asin(")),
+				self.create_button("acos", Message::InsertText("acos(")),
+				self.create_button("atan", Message::InsertText("atan(")),
+				self.create_button("atan2", Message::InsertText("atan2(")),
+			].spacing(spacing).align_items(Alignment::Center),
+			// Reciprocal trigonometric functions and the exponential
+			row![
+				self.create_button("sec", Message::InsertText("sec(")),
+				self.create_button("csc", Message::InsertText("csc(")),
+				self.create_button("cot", Message::InsertText("cot(")),
+				self.create_button("exp", Message::InsertText("exp(")),
+			].spacing(spacing).align_items(Alignment::Center),
+		].spacing(spacing).into()
+	}
+	
 	fn create_button<'a>(&self, label: &str, message: Message) -> Element<'a, Message> {		
 		let btn = button(text(label)
 				.horizontal_alignment(Horizontal::Center)
@@ -303,7 +760,314 @@ impl Calculator {
 		btn.into()
 	}
 	
-	fn create_history_panel(&self) -> Element<Message> {
+	fn create_approximation_panel(&self) -> Element<'_, Message> {
+		let grey = iced::theme::Text::Color(iced::Color::from_rgb(0.7, 0.7, 0.7));
+		let mut items = column![
+			row![
+				text("Approximate").size(16),
+				Space::with_width(Length::Fill),
+				text("Max denominator").size(12),
+				Space::with_width(6),
+				text_input("1000", &self.denominator_bound_input)
+					.on_input(Message::DenominatorBoundChanged)
+					.size(12)
+					.width(Length::Fixed(70.0)),
+			].align_items(Alignment::Center)
+		].spacing(8);
+		
+		let Some(value) = &self.last_value else {
+			items = items.push(text("Calculate a result to approximate it").size(14).style(grey));
+			return self.wrap_side_panel(items);
+		};
+		
+		// Continued fraction, cut short with an ellipsis after MAX_TERMS terms
+		const MAX_TERMS: usize = 30;
+		let terms = match value.continued_fraction(MAX_TERMS + 1) {
+			Ok(terms) => terms,
+			Err(message) => {
+				items = items.push(text(message).size(14).style(grey));
+				return self.wrap_side_panel(items);
+			}
+		};
+		let mut expansion = format!("[{}", terms[0]);
+		for (index, term) in terms.iter().enumerate().skip(1).take(MAX_TERMS - 1) {
+			expansion.push_str(if index == 1 { "; " } else { ", " });
+			expansion.push_str(&term.to_string());
+		}
+		if terms.len() > MAX_TERMS {
+			expansion.push_str(", …");
+		}
+		expansion.push(']');
+		items = items.push(column![
+			text("Continued fraction").size(12).style(grey),
+			text(expansion).size(12),
+		]);
+		
+		let bound = match self.denominator_bound_input.trim().parse::<BigInt>() {
+			Ok(bound) if bound >= BigInt::from(1) => bound,
+			_ => {
+				items = items.push(text("Max denominator must be a positive integer").size(12)
+					.style(iced::theme::Text::Color(iced::Color::from_rgb(1.0, 0.4, 0.4))));
+				return self.wrap_side_panel(items);
+			}
+		};
+		
+		// Each approximation can be clicked to put it in the input
+		items = items.push(text("Best approximations").size(12).style(grey));
+		for approximation in value.best_approximations(&bound).unwrap_or_default() {
+			let decimal = approximation.to_decimal(&self.context).to_display_string(&self.context);
+			items = items.push(
+				button(text(format!("{} ≈ {}", approximation, decimal)).size(12))
+					.on_press(Message::InputChanged(approximation.to_string()))
+					.style(iced::theme::Button::Text)
+					.padding(2)
+			);
+		}
+		
+		self.wrap_side_panel(items)
+	}
+	
+	// In programmer mode integer results are wrapped into the word, with flags for what
+	// that changed; anything else is shown as it is
+	fn wrap_to_word(&mut self, value: Value) -> Result<Value, String> {
+		self.word_flags = None;
+		if !self.show_programmer || !value.is_integer() {
+			return Ok(value);
+		}
+		let (word, flags) = value.to_decimal(&self.context)?.to_word(&self.context)?;
+		self.word_flags = Some(flags);
+		Value::from_literal(&word, &self.context)
+	}
+	
+	fn create_programmer_panel(&self) -> Element<'_, Message> {
+		let grey = iced::theme::Text::Color(iced::Color::from_rgb(0.7, 0.7, 0.7));
+		let spacing = 6;
+		let flags = self.word_flags.unwrap_or_default();
+		let flag = |name: &str, set: bool| {
+			let color = if set { iced::Color::from_rgb(1.0, 0.6, 0.2) } else { iced::Color::from_rgb(0.4, 0.4, 0.4) };
+			text(name.to_string()).size(12).style(iced::theme::Text::Color(color))
+		};
+		let mut items = column![
+			row![
+				text("Programmer").size(16),
+				Space::with_width(Length::Fill),
+				pick_list(
+					&WordSize::ALL[..],
+					Some(self.context.word_size),
+					Message::WordSizeSelected
+				)
+				.text_size(14)
+				.width(Length::Fixed(110.0)),
+			].align_items(Alignment::Center),
+			row![
+				checkbox("Signed", self.context.signed)
+					.on_toggle(Message::SignedToggled)
+					.size(14)
+					.text_size(14),
+				Space::with_width(Length::Fill),
+				tooltip(
+					row![flag("CARRY", flags.carry), Space::with_width(8), flag("OVERFLOW", flags.overflow)],
+					text("The result does not fit the word as unsigned (carry) or signed (overflow)").size(12),
+					tooltip::Position::Bottom,
+				)
+				.style(iced::theme::Container::Box),
+			].align_items(Alignment::Center),
+			// Bitwise operators, with rotation inside the word
+			row![
+				self.create_button("AND", Message::InsertText(" & ")),
+				self.create_button("OR", Message::InsertText(" | ")),
+				self.create_button("XOR", Message::InsertText(" xor ")),
+				self.create_button("NOT", Message::InsertText("~")),
+			].spacing(spacing),
+			row![
+				self.create_button("<<", Message::InsertText(" << ")),
+				self.create_button(">>", Message::InsertText(" >> ")),
+				self.create_button("ROL", Message::InsertText("rol(")),
+				self.create_button("ROR", Message::InsertText("ror(")),
+			].spacing(spacing),
+			// Hexadecimal digits and the prefixes of other bases
+			row![
+				self.create_button("A", Message::AddDigit('A')),
+				self.create_button("B", Message::AddDigit('B')),
+				self.create_button("C", Message::AddDigit('C')),
+				self.create_button("0x", Message::InsertText("0x")),
+			].spacing(spacing),
+			row![
+				self.create_button("D", Message::AddDigit('D')),
+				self.create_button("E", Message::AddDigit('E')),
+				self.create_button("F", Message::AddDigit('F')),
+				self.create_button("0b", Message::InsertText("0b")),
+			].spacing(spacing),
+		].spacing(8);
+		
+		let word = match &self.last_value {
+			Some(value) if value.is_integer() => value.to_decimal(&self.context).ok(),
+			_ => None,
+		};
+		let Some(word) = word else {
+			items = items.push(text("Calculate an integer to see its bits").size(14).style(grey));
+			return self.wrap_side_panel(items);
+		};
+		
+		// The same word in four bases at once; the other bases show its bit pattern
+		let decimal = Context { radix: 10, display_limit: MAX_DISPLAY_LIMIT, ..self.context.clone() };
+		let views = [
+			("HEX", word.word_digits(16, &self.context).unwrap_or_default()),
+			("DEC", Value::Decimal(word.clone()).to_display_string(&decimal)),
+			("OCT", word.word_digits(8, &self.context).unwrap_or_default()),
+			("BIN", group_digits(&word.word_digits(2, &self.context).unwrap_or_default(), 4)),
+		];
+		for (label, digits) in views {
+			items = items.push(row![
+				text(label).size(12).style(grey).width(Length::Fixed(32.0)),
+				text(digits).size(12),
+			]);
+		}
+		
+		// Bit grid, most significant bit first, 16 bits to a row; clicking a bit flips it
+		const MAX_GRID_BITS: u32 = 128;
+		let width = word.word_width(&self.context).unwrap_or(0);
+		if width > MAX_GRID_BITS {
+			items = items.push(text("Too wide for the bit grid").size(12).style(grey));
+			return self.wrap_side_panel(items);
+		}
+		let bits = word.word_digits(2, &self.context).unwrap_or_default();
+		let bits = format!("{:0>width$}", bits, width = width as usize);
+		let mut grid = column![].spacing(2);
+		for row_start in (0..width).step_by(16).rev() {
+			let top = (row_start + 15).min(width - 1);
+			let mut bit_row = row![text(top.to_string()).size(10).style(grey).width(Length::Fixed(22.0))]
+				.spacing(0)
+				.align_items(Alignment::Center);
+			for bit in (row_start..=top).rev() {
+				let set = bits.as_bytes()[(width - 1 - bit) as usize] == b'1';
+				bit_row = bit_row.push(
+					button(text(if set { "1" } else { "0" }).size(11).horizontal_alignment(Horizontal::Center))
+						.on_press(Message::ToggleBit(bit))
+						.style(if set { iced::theme::Button::Primary } else { iced::theme::Button::Secondary })
+						.padding(1)
+						.width(Length::Fixed(14.0))
+				);
+				if bit % 4 == 0 && bit != row_start {
+					bit_row = bit_row.push(Space::with_width(4));
+				}
+			}
+			grid = grid.push(bit_row);
+		}
+		items = items.push(grid);
+		
+		self.wrap_side_panel(items)
+	}
+	
+	// The last result rounded to an IEEE 754 format: its exact value, its bit fields and how
+	// far rounding moved it. When emulating f64 it also lists the literals that were rounded.
+	fn create_float_panel(&self) -> Element<'_, Message> {
+		let grey = iced::theme::Text::Color(iced::Color::from_rgb(0.7, 0.7, 0.7));
+		let field = |label: &str, value: String| column![
+			text(label.to_string()).size(12).style(grey),
+			text(value).size(12),
+		];
+		let mut items = column![
+			row![
+				text("IEEE 754").size(16),
+				Space::with_width(Length::Fill),
+				pick_list(
+					&FloatFormat::ALL[..],
+					Some(self.float_format),
+					Message::FloatFormatSelected
+				)
+				.text_size(14)
+				.width(Length::Fixed(110.0)),
+			].align_items(Alignment::Center),
+			checkbox("Emulate f64", self.context.emulate_f64)
+				.on_toggle(Message::EmulateToggled)
+				.size(14)
+				.text_size(14),
+		].spacing(8);
+		
+		let exact = match self.last_value.as_ref().map(Value::to_rational) {
+			Some(Ok(exact)) => exact,
+			Some(Err(message)) => {
+				items = items.push(text(message).size(14).style(grey));
+				return self.wrap_side_panel(items);
+			}
+			None => {
+				items = items.push(text("Calculate a result to see it as a float").size(14).style(grey));
+				return self.wrap_side_panel(items);
+			}
+		};
+		let format = self.float_format;
+		let float = Float::nearest(&exact, format);
+		
+		// Every finite float is a terminating decimal, shown in full
+		let full = Context { radix: 10, display_limit: MAX_DISPLAY_LIMIT, ..self.context.clone() };
+		let sign = if float.negative { "-" } else { "" };
+		let value = match float.to_exact_decimal() {
+			Some(value) if value.is_zero() => format!("{}0", sign),
+			Some(value) => Value::Decimal(value).to_display_string(&full),
+			None => format!("{}∞", sign),
+		};
+		items = items.push(field(&format!("Nearest {}", format), value));
+		
+		let class = if float.is_infinite() {
+			"infinity, too large for the format".to_string()
+		} else if float.is_zero() {
+			"zero".to_string()
+		} else if float.is_subnormal() {
+			format!("subnormal, 2^{} with leading zeros", float.unbiased_exponent())
+		} else {
+			format!("normal, 2^{}", float.unbiased_exponent())
+		};
+		let total_bits = 1 + format.exponent_bits() + format.fraction_bits();
+		let hex = format!("{:0>width$}", float.to_bits().to_str_radix(16).to_uppercase(), width = total_bits as usize / 4);
+		let fraction = format!("{:0>width$}", float.fraction.to_str_radix(2), width = format.fraction_bits() as usize);
+		items = items.push(field("Bits", format!("0x{}", group_digits(&hex, 4))));
+		items = items.push(field("Sign", if float.negative { "1 (negative)".to_string() } else { "0 (positive)".to_string() }));
+		items = items.push(field("Exponent", format!("{:0>width$b}: {}", float.exponent, class, width = format.exponent_bits() as usize)));
+		items = items.push(field("Fraction", group_digits(&fraction, 4)));
+		
+		// Errors are tiny, so they keep significant digits rather than decimal places
+		let rounding = self.context.rounding;
+		match (float.rounding_error(&exact), float.error_in_ulps(&exact)) {
+			(Some(error), _) if error.is_zero() => {
+				items = items.push(field("Rounding error", "none, the value is exact".to_string()));
+			}
+			(Some(error), Some(ulps)) => {
+				items = items.push(field("Rounding error", error.to_significant(6, rounding).to_display_string(&self.context)));
+				if let Ok(relative) = error.divide(&exact) {
+					items = items.push(field("Relative error", relative.to_significant(3, rounding).to_display_string(&self.context)));
+				}
+				items = items.push(field("Units in the last place", ulps.to_significant(3, rounding).to_display_string(&self.context)));
+			}
+			_ => {
+				items = items.push(field("Rounding error", "infinite".to_string()));
+			}
+		}
+		
+		if !self.float_literals.is_empty() {
+			items = items.push(text("Literals rounded to f64").size(12).style(grey));
+			for (literal, float) in &self.float_literals {
+				let rounded = float.to_exact_decimal().map(|value| Value::Decimal(value).to_display_string(&full)).unwrap_or("∞".to_string());
+				items = items.push(text(format!("{} → {}", Value::Exact(literal.clone()).to_display_string(&self.context), rounded)).size(12));
+			}
+		}
+		
+		self.wrap_side_panel(items)
+	}
+	
+	fn wrap_side_panel<'a>(&self, items: iced::widget::Column<'a, Message>) -> Element<'a, Message> {
+		container(
+			scrollable(items)
+				.height(Length::Fill)
+		)
+		.padding(15)
+		.width(Length::Fixed(300.0))
+		.height(Length::Fill)
+		.style(iced::theme::Container::Box)
+		.into()
+	}
+	
+	fn create_history_panel(&self) -> Element<'_, Message> {
 		let mut history_items = column![];
 		
 		// Add header with navigation info
@@ -373,102 +1137,89 @@ impl Calculator {
 			);
 		}
 		
-		container(
-			scrollable(history_items)
-				.height(Length::Fill)
-		)
-		.padding(15)
-		.width(Length::Fixed(300.0))
-		.height(Length::Fill)
-		.style(iced::theme::Container::Box)
-		.into()
+		self.wrap_side_panel(history_items)
 	}
 }
 
-fn evaluate_expression(expr: &str) -> Result<String, String> {
-	let tokens: Vec<&str> = expr.split_whitespace().collect();
-	
-	if tokens.is_empty() {
-		return Ok("0".to_string());
-	}
-	
-	// Handle single number case
-	if tokens.len() == 1 {
-		return parse_number(tokens[0]).map(|bd| bd.to_string());
-	}
-
-	let mut numbers: Vec<BigNumber> = Vec::new();
-	let mut operators: Vec<char> = Vec::new();
-
-	for token in tokens {
-		if let Ok(num) = parse_number(token) {
-			numbers.push(num);
-		} else if let Some(op) = token.chars().next() {
-			if "+-*/^".contains(op) && token.len() == 1 {
-				while let Some(&last_op) = operators.last() {
-					if precedence(last_op) >= precedence(op) {
-						apply_operation(&mut numbers, last_op)?;
-						operators.pop();
-					} else {
-						break;
-					}
-				}
-				operators.push(op);
-			} else {
-				return Err(format!("Invalid operator: {}", token));
-			}
+// Digits in groups of `size` from the right, like 1 0110 1001
+fn group_digits(digits: &str, size: usize) -> String {
+	let chars: Vec<char> = digits.chars().collect();
+	let mut grouped = String::new();
+	for (index, c) in chars.iter().enumerate() {
+		if index > 0 && (chars.len() - index).is_multiple_of(size) {
+			grouped.push(' ');
 		}
+		grouped.push(*c);
 	}
-
-	while let Some(op) = operators.pop() {
-		apply_operation(&mut numbers, op)?;
-	}
-
-	if numbers.len() != 1 {
-		return Err("Invalid expression".to_string());
-	}
-
-	Ok(numbers.pop().unwrap().to_string_with_limit(25))
+	grouped
 }
 
-fn precedence(op: char) -> u8 {
-	match op {
-		'+' | '-' => 1,
-		'*' | '/' => 2,
-		'^' => 3,
-		_ => 0,
+fn evaluate_input(expr: &str, ctx: &Context) -> Result<Value, String> {
+	let tokens = tokenize(expr)?;
+	
+	if tokens.is_empty() {
+		return Ok(Value::Exact(Rational::from_integer(BigInt::zero())));
+	}
+	
+	let ast = parse(tokens)?;
+	if ctx.emulate_f64 {
+		return evaluate_f64(&ast, ctx);
 	}
+	evaluate(&ast, ctx)
 }
 
-fn apply_operation(numbers: &mut Vec<BigNumber>, op: char) -> Result<(), String> {
-	if numbers.len() < 2 {
-		return Err("Not enough operands".to_string());
+// An input's result as the display shows it, or its error, for the tests of every module
+#[cfg(test)]
+fn calculate(input: &str, ctx: &Context) -> String {
+	match evaluate_input(input, ctx) {
+		Ok(value) => value.to_display_string(ctx),
+		Err(e) => format!("Error: {}", e),
 	}
-
-	let b = numbers.pop().unwrap();
-	let a = numbers.pop().unwrap();
-
-	let result = match op {
-		'+' => Ok(a.add(&b)),
-		'-' => Ok(a.subtract(&b)),
-		'*' => Ok(a.multiply(&b)),
-		'/' => a.divide(&b, 15), // 15 decimal places precision
-		'^' => a.power(&b),
-		_ => Err(format!("Unknown operator: {}", op)),
-	}?;
-
-	numbers.push(result);
-	Ok(())
 }
 
 fn main() -> iced::Result {
 	Calculator::run(Settings {
 		window: iced::window::Settings {
-			size: Size::new(320.0, 450.0), // Increased height for new button row
+			size: Size::new(320.0, 625.0), // Room for the settings rows and all button rows
 			resizable: true,
-			min_size: Some(Size::new(320.0, 450.0)),
+			min_size: Some(Size::new(320.0, 625.0)),
 			..Default::default()
 		},
 		..Default::default()
 	})
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn backspace_removes_whole_characters() {
+		let mut calculator = Calculator::with_context(Context::default());
+		for key in ["√", "π", "∠"] {
+			let _ = calculator.update(Message::InputChanged(format!("2{}", key)));
+			let _ = calculator.update(Message::Backspace);
+			assert_eq!(calculator.current_input, "2");
+		}
+		let _ = calculator.update(Message::InputChanged("√".to_string()));
+		let _ = calculator.update(Message::Backspace);
+		assert_eq!(calculator.current_input, "0");
+	}
+
+	#[test]
+	fn dms_conversion_runs_in_the_background() {
+		let mut calculator = Calculator::with_context(Context::default());
+		let _ = calculator.update(Message::InputChanged("12.5".to_string()));
+		let _ = calculator.update(Message::ConvertToDms);
+		assert!(calculator.running.is_some());
+		assert_eq!(calculator.current_input, "12.5");
+		let _ = calculator.update(Message::CancelCalculation);
+		assert!(calculator.running.is_none());
+	}
+
+	#[test]
+	fn digits_group_from_the_right() {
+		assert_eq!(group_digits("1101001", 4), "110 1001");
+		assert_eq!(group_digits("FF", 4), "FF");
+	}
+}
@@ -2,8 +2,78 @@
 
 
 use num_bigint::BigInt;
-use num_traits::{Zero, One};
+use num_traits::{Zero, One, Signed, ToPrimitive};
 use regex::Regex;
+use std::fmt;
+
+use crate::context::{Context, FractionStyle};
+
+mod bitwise;
+mod combinatorics;
+mod complex;
+mod constants;
+mod hyperbolic;
+mod ieee754;
+mod number_theory;
+mod primes;
+mod radix;
+mod rational;
+mod trig;
+
+pub use bitwise::WordFlags;
+pub use complex::Complex;
+pub use ieee754::{Float, FloatFormat};
+pub use radix::radix_prefix;
+pub use rational::Rational;
+
+// How digits beyond the target precision are discarded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RoundingMode {
+	#[default]
+	HalfEven,
+	HalfUp,
+	HalfDown,
+	Floor,
+	Ceiling,
+	Up,
+	Down,
+}
+
+impl RoundingMode {
+	// The mode that rounds -x the way this mode rounds x
+	pub fn mirrored(self) -> Self {
+		match self {
+			RoundingMode::Floor => RoundingMode::Ceiling,
+			RoundingMode::Ceiling => RoundingMode::Floor,
+			other => other,
+		}
+	}
+	
+	pub const ALL: [RoundingMode; 7] = [
+		RoundingMode::HalfEven,
+		RoundingMode::HalfUp,
+		RoundingMode::HalfDown,
+		RoundingMode::Floor,
+		RoundingMode::Ceiling,
+		RoundingMode::Up,
+		RoundingMode::Down,
+	];
+}
+
+impl fmt::Display for RoundingMode {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			RoundingMode::HalfEven => "Half even",
+			RoundingMode::HalfUp => "Half up",
+			RoundingMode::HalfDown => "Half down",
+			RoundingMode::Floor => "Floor",
+			RoundingMode::Ceiling => "Ceiling",
+			RoundingMode::Up => "Up",
+			RoundingMode::Down => "Down",
+		};
+		write!(f, "{}", name)
+	}
+}
 
 // Custom BigDecimal implementation for high precision arithmetic
 #[derive(Debug, Clone, PartialEq)]
@@ -26,7 +96,7 @@ impl BigNumber {
 		}
 		
 		// Check for scientific notation (case insensitive, with optional whitespace)
-		if s.contains(|c| c == 'e' || c == 'E') {
+		if s.contains(['e', 'E']) {
 			return Self::from_scientific(s);
 		}
 		
@@ -73,6 +143,30 @@ impl BigNumber {
 		}
 	}
 
+	// Repeating decimal such as "0.(142857)" or "0.1(6)", where the digits in parentheses
+	// repeat forever; the value is the exact fraction it stands for
+	fn from_repeating(s: &str) -> Result<Rational, String> {
+		let invalid = || format!("Invalid repeating decimal '{}'", s);
+		let (fixed, repetend) = s.trim()
+			.strip_suffix(')')
+			.and_then(|s| s.split_once('('))
+			.ok_or_else(invalid)?;
+		if repetend.is_empty() || !repetend.chars().all(|c| c.is_ascii_digit()) {
+			return Err(invalid());
+		}
+		if !fixed.contains('.') {
+			return Err("Repeating digits must follow the decimal point".to_string());
+		}
+		
+		// x = fixed + repetend / (10^k * (10^r - 1)) with k fixed decimals and r repeating digits
+		let prefix = Self::from_decimal(fixed)?;
+		let repetend_value = repetend.parse::<BigInt>().map_err(|_| invalid())?;
+		let period = pow10(repetend.len() as u32) - 1;
+		let repeating = Rational::new(repetend_value, pow10(prefix.scale as u32) * period)?;
+		let repeating = if fixed.trim_start().starts_with('-') { repeating.negate() } else { repeating };
+		Ok(prefix.to_rational().add(&repeating))
+	}
+
 	fn from_scientific(s: &str) -> Result<Self, String> {
 		let s = s.trim();
 		
@@ -122,12 +216,13 @@ impl BigNumber {
 	
 	fn align_scales(&self, other: &Self) -> (BigNumber, BigNumber) {
 		let max_scale = self.scale.max(other.scale);
-		let left = self.scale_to(max_scale);
-		let right = other.scale_to(max_scale);
+		// Only ever raises the scale, so no digits are dropped
+		let left = self.scale_to(max_scale, RoundingMode::Down);
+		let right = other.scale_to(max_scale, RoundingMode::Down);
 		(left, right)
 	}
 	
-	fn scale_to(&self, target_scale: i32) -> Self {
+	pub fn scale_to(&self, target_scale: i32, rounding: RoundingMode) -> Self {
 		if self.scale == target_scale {
 			return self.clone();
 		}
@@ -138,10 +233,18 @@ impl BigNumber {
 			Self::new(&self.mantissa * factor, target_scale)
 		} else {
 			let factor = BigInt::from(10).pow((-scale_diff) as u32);
-			Self::new(&self.mantissa / factor, target_scale)
+			Self::new(divide_rounded(&self.mantissa, &factor, rounding), target_scale)
 		}
 	}
 	
+	pub fn negate(&self) -> Self {
+		Self::new(-&self.mantissa, self.scale)
+	}
+	
+	pub fn abs(&self) -> Self {
+		Self::new(self.mantissa.abs(), self.scale)
+	}
+	
 	pub fn add(&self, other: &Self) -> Self {
 		let (left, right) = self.align_scales(other);
 		Self::new(&left.mantissa + &right.mantissa, left.scale).normalize()
@@ -158,67 +261,427 @@ impl BigNumber {
 		Self::new(mantissa, scale).normalize()
 	}
 	
-	pub fn divide(&self, other: &Self, precision: i32) -> Result<Self, String> {
+	pub fn divide(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
 		if other.mantissa.is_zero() {
 			return Err("Division by zero".to_string());
 		}
 		
-		// Scale up the dividend to achieve desired precision
-		let scale_up = precision + other.scale - self.scale;
-		let dividend = if scale_up > 0 {
-			&self.mantissa * BigInt::from(10).pow(scale_up as u32)
+		// Shift whichever side is needed so the quotient lands at the desired precision
+		let shift = ctx.precision + other.scale - self.scale;
+		let (dividend, divisor) = if shift >= 0 {
+			(&self.mantissa * BigInt::from(10).pow(shift as u32), other.mantissa.clone())
 		} else {
-			self.mantissa.clone()
+			(self.mantissa.clone(), &other.mantissa * BigInt::from(10).pow((-shift) as u32))
 		};
 		
-		let quotient = dividend / &other.mantissa;
-		let result_scale = if scale_up > 0 { 
-			scale_up 
-		} else { 
-			self.scale - other.scale 
-		};
+		let quotient = divide_rounded(&dividend, &divisor, ctx.rounding);
+		Ok(Self::new(quotient, ctx.precision).normalize())
+	}
+	
+	pub fn power(&self, exponent: &Self, ctx: &Context) -> Result<Self, String> {
+		if !exponent.is_integer() {
+			return self.real_power(exponent, ctx);
+		}
 		
-		Ok(Self::new(quotient, result_scale).normalize())
+		let exp_int = exponent.to_integer();
+		if exp_int.is_zero() {
+			return Ok(Self::new(BigInt::one(), 0));
+		}
+		if self.is_zero() {
+			if exp_int.is_negative() {
+				return Err("Zero cannot be raised to a negative power".to_string());
+			}
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		
+		let base = self.normalize();
+		let exp_abs = exp_int.abs();
+		
+		// |x^n| = 10^(n * log10|x|); a negative power that small truncates to zero
+		let result_log10 = exp_abs.to_f64().unwrap_or(f64::INFINITY) * base.log10_estimate();
+		if exp_int.is_negative() && result_log10 > (ctx.precision + 1) as f64 {
+			let odd = !(&exp_abs & BigInt::one()).is_zero();
+			let negative = base.is_negative() && odd;
+			return Ok(Self::round_tiny(negative, ctx));
+		}
+		
+		let exp_u64 = base.check_power_size(&exp_abs)?;
+		let positive = base.integer_power(exp_u64);
+		
+		if exp_int.is_negative() {
+			// x^-n = 1 / x^n
+			Self::new(BigInt::one(), 0).divide(&positive, ctx)
+		} else if positive.scale > ctx.precision {
+			// Powers of inexact decimals keep every digit of the product, so they are
+			// rounded to the precision like quotients are
+			Ok(positive.scale_to(ctx.precision, ctx.rounding).normalize())
+		} else {
+			Ok(positive)
+		}
 	}
 	
-	pub fn power(&self, exponent: &Self) -> Result<Self, String> {
-		// Simple integer power implementation
-		if exponent.scale > 0 {
-			return Err("Non-integer exponents not supported".to_string());
+	// Rejects powers whose mantissa or scale would grow past MAX_RESULT_DIGITS,
+	// returning the exponent as a machine integer once it is known to be safe
+	fn check_power_size(&self, exponent: &BigInt) -> Result<u64, String> {
+		let exp_f64 = exponent.to_f64().unwrap_or(f64::INFINITY);
+		let mantissa_log10 = self.log10_estimate() + self.scale as f64;
+		check_result_digits(exp_f64 * mantissa_log10)?;
+		check_result_digits(exp_f64 * self.scale.abs() as f64)?;
+		
+		if self.mantissa.abs().is_one() && self.scale == 0 {
+			// (+-1)^n only depends on the parity of n
+			let odd = !(exponent & BigInt::one()).is_zero();
+			return Ok(if odd { 1 } else { 2 });
 		}
+		exponent.to_u64().ok_or_else(|| "Exponent too large".to_string())
+	}
+	
+	// Square-and-multiply on the mantissa; the scale just multiplies by the exponent
+	fn integer_power(&self, exponent: u64) -> Self {
+		let mut result = BigInt::one();
+		let mut base = self.mantissa.clone();
+		let mut remaining = exponent;
 		
-		let exp_int = exponent.mantissa.to_string().parse::<i32>()
-			.map_err(|_| "Exponent too large")?;
+		while remaining > 0 {
+			if remaining & 1 == 1 {
+				result *= &base;
+			}
+			remaining >>= 1;
+			if remaining > 0 {
+				base = &base * &base;
+			}
+		}
 		
-		if exp_int < 0 {
-			return Err("Negative exponents not supported".to_string());
+		Self::new(result, self.scale * exponent as i32).normalize()
+	}
+	
+	// x^y = exp(y * ln x) for non-integer y
+	fn real_power(&self, exponent: &Self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			if exponent.is_negative() {
+				return Err("Zero cannot be raised to a negative power".to_string());
+			}
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		if self.is_negative() {
+			return Err("Negative base requires an integer exponent".to_string());
 		}
 		
-		if exp_int == 0 {
+		// The error in y*ln(x) becomes a relative error of the result, so a large
+		// result needs as many extra digits as it has integer digits
+		let result_log10 = exponent.to_f64() * self.log10_estimate();
+		check_result_digits(result_log10)?;
+		let extra_digits = if result_log10 > 0.0 { result_log10.ceil() as i32 } else { 0 };
+		let ln_ctx = ctx.with_precision(ctx.precision + extra_digits + 5).with_rounding(RoundingMode::HalfEven);
+		
+		let log = self.ln(&ln_ctx)?;
+		let product = log.multiply(exponent).scale_to(ln_ctx.precision, ln_ctx.rounding);
+		product.exp(ctx)
+	}
+	
+	pub fn exp(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
 			return Ok(Self::new(BigInt::one(), 0));
 		}
+		if self.is_negative() {
+			if -self.to_f64() * std::f64::consts::LOG10_E > (ctx.precision + 1) as f64 {
+				return Ok(Self::round_tiny(false, ctx));
+			}
+			// exp(-x) = 1 / exp(x); exp(x) >= 1 so its absolute error bounds the relative error
+			let guarded = ctx.with_precision(ctx.precision + 2).with_rounding(RoundingMode::HalfEven);
+			let positive = self.negate().exp(&guarded)?;
+			return Self::new(BigInt::one(), 0).divide(&positive, ctx);
+		}
+		
+		let approx = self.to_f64();
+		let integer_digits = approx * std::f64::consts::LOG10_E;
+		check_result_digits(integer_digits)?;
 		
-		let mut result = self.clone();
-		for _ in 1..exp_int {
-			result = result.multiply(self);
+		// Every squaring below doubles the error, so each halving costs one guard bit
+		let base_bits = decimal_to_bits(ctx.precision as f64 + integer_digits) + 32;
+		let extra_halvings = (base_bits as f64).sqrt() as u64;
+		let bits = base_bits + approx.log2().max(0.0).ceil() as u64 + extra_halvings;
+		
+		// Halve the argument well below 1 so the series converges quickly, then square back up
+		let mut reduced = self.to_fixed(bits);
+		let threshold = BigInt::one() << (bits - extra_halvings);
+		let mut halvings = 0;
+		while reduced >= threshold {
+			reduced >>= 1;
+			halvings += 1;
+		}
+		
+		// Taylor series: sum of r^n / n!
+		let mut sum = BigInt::one() << bits;
+		let mut term = sum.clone();
+		let mut n = 1u32;
+		loop {
+			term = ((term * &reduced) >> bits) / n;
+			if term.is_zero() {
+				break;
+			}
+			sum += &term;
+			n += 1;
 		}
 		
-		Ok(result.normalize())
+		for _ in 0..halvings {
+			sum = (&sum * &sum) >> bits;
+		}
+		
+		Ok(Self::from_fixed(&sum, bits, ctx))
+	}
+	
+	pub fn ln(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() || self.is_negative() {
+			return Err("Logarithm is only defined for positive numbers".to_string());
+		}
+		
+		// x = a * 10^d with 1 <= a < 10, so ln(x) = ln(a) + d*ln(10)
+		let digits = self.mantissa.to_string().len() as i64;
+		let decimal_exponent = digits - 1 - self.scale as i64;
+		let base_bits = decimal_to_bits(ctx.precision as f64) + 32 + (decimal_exponent.unsigned_abs() + 1).ilog2() as u64;
+		let square_roots = (base_bits as f64 / 100.0).sqrt() as u64;
+		let bits = base_bits + square_roots;
+		let one = BigInt::one() << bits;
+		
+		let mut reduced = (&self.mantissa << bits) / pow10((digits - 1) as u32);
+		
+		// Bring a into [0.75, 1.5) by halving, then closer to 1 with repeated square roots:
+		// ln(a) = 2^(k+1) * atanh((a^(1/2^k) - 1) / (a^(1/2^k) + 1))
+		let limit = &one * 3 / 2;
+		let mut halvings = 0;
+		while reduced >= limit {
+			reduced >>= 1;
+			halvings += 1;
+		}
+		for _ in 0..square_roots {
+			reduced = integer_root(&(reduced << bits), 2);
+		}
+		let z = ((&reduced - &one) << bits) / (&reduced + &one);
+		let ln_reduced = atanh_fixed(&z, bits) << (square_roots + 1);
+		
+		let sum = ln_reduced + ln2_fixed(bits) * halvings + ln10_fixed(bits) * decimal_exponent;
+		Ok(Self::from_fixed(&sum, bits, ctx))
+	}
+	
+	pub fn log10(&self, ctx: &Context) -> Result<Self, String> {
+		self.log(&Self::new(BigInt::from(10), 0), ctx)
+	}
+	
+	pub fn log2(&self, ctx: &Context) -> Result<Self, String> {
+		self.log(&Self::new(BigInt::from(2), 0), ctx)
+	}
+	
+	pub fn log(&self, base: &Self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() || self.is_negative() {
+			return Err("Logarithm is only defined for positive numbers".to_string());
+		}
+		if base.is_zero() || base.is_negative() {
+			return Err("Logarithm base must be positive".to_string());
+		}
+		if base.normalize() == Self::new(BigInt::one(), 0) {
+			return Err("Logarithm base must not be 1".to_string());
+		}
+		
+		// Exact powers like log10(1000) or log2(0.125) should not pick up rounding noise
+		if let Some(power) = self.exact_log(base) {
+			return Ok(Self::new(BigInt::from(power), 0));
+		}
+		
+		// A base close to 1 has a tiny logarithm that magnifies the error of the quotient
+		let base_log10 = base.log10_estimate();
+		let base_ln_size = if base_log10.abs() < 1e-3 {
+			// ln(b) is about (b - 1) here, which the estimate above cannot resolve
+			base.subtract(&Self::new(BigInt::one(), 0)).log10_estimate()
+		} else {
+			(base_log10 * std::f64::consts::LN_10).abs().log10()
+		};
+		let extra_digits = (-base_ln_size).max(0.0).ceil() as i32;
+		let guarded = ctx.with_precision(ctx.precision + extra_digits + 10).with_rounding(RoundingMode::HalfEven);
+		
+		let numerator = self.ln(&guarded)?;
+		let denominator = base.ln(&guarded)?;
+		numerator.divide(&denominator, ctx)
+	}
+	
+	// Returns k when self is exactly base^k for an integer base >= 2
+	fn exact_log(&self, base: &Self) -> Option<i64> {
+		if !base.is_integer() {
+			return None;
+		}
+		let base = base.to_integer();
+		if base < BigInt::from(2) {
+			return None;
+		}
+		
+		let value = self.strip_trailing_zeros();
+		let (target, sign) = if value.scale <= 0 {
+			(&value.mantissa * pow10((-value.scale) as u32), 1)
+		} else {
+			// 1 / x = 10^s / m must be an integer power of the base
+			let numerator = pow10(value.scale as u32);
+			if !(&numerator % &value.mantissa).is_zero() {
+				return None;
+			}
+			(numerator / &value.mantissa, -1)
+		};
+		
+		let estimate = value.log10_estimate().abs() / Self::new(base.clone(), 0).log10_estimate();
+		let power = estimate.round() as u32;
+		if base.pow(power) == target {
+			Some(sign * power as i64)
+		} else {
+			None
+		}
+	}
+	
+	// Like normalize, but also moves trailing zeros of integers into a negative scale
+	fn strip_trailing_zeros(&self) -> Self {
+		if self.mantissa.is_zero() {
+			return Self::new(BigInt::zero(), 0);
+		}
+		let digits = self.mantissa.to_string();
+		let zeros = digits.len() - digits.trim_end_matches('0').len();
+		Self::new(&self.mantissa / pow10(zeros as u32), self.scale - zeros as i32)
+	}
+	
+	// Binary fixed point: the returned integer represents x * 2^bits
+	fn to_fixed(&self, bits: u64) -> BigInt {
+		if self.scale >= 0 {
+			(&self.mantissa << bits) / pow10(self.scale as u32)
+		} else {
+			(&self.mantissa * pow10((-self.scale) as u32)) << bits
+		}
+	}
+	
+	fn from_fixed(value: &BigInt, bits: u64, ctx: &Context) -> Self {
+		let scaled = value * pow10(ctx.precision as u32);
+		let mantissa = divide_rounded(&scaled, &(BigInt::one() << bits), ctx.rounding);
+		Self::new(mantissa, ctx.precision).normalize()
+	}
+	
+	pub fn sqrt(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_negative() {
+			return Err("Square root of a negative number".to_string());
+		}
+		self.nth_root(2, ctx)
+	}
+	
+	pub fn nth_root(&self, n: u32, ctx: &Context) -> Result<Self, String> {
+		if n == 0 {
+			return Err("Root degree must be a positive integer".to_string());
+		}
+		if self.is_negative() && n.is_multiple_of(2) {
+			return Err(format!("Even root ({}) of a negative number", n));
+		}
+		if self.is_zero() || n == 1 {
+			return Ok(self.normalize());
+		}
+		
+		// root(m * 10^-s) * 10^p = root(m * 10^(n*p - s)); when that exponent is negative,
+		// pull out a power of ten divisible by n so the radicand stays an integer
+		let exponent = n as i64 * ctx.precision as i64 - self.scale as i64;
+		check_result_digits(exponent as f64)?;
+		let extra = if exponent >= 0 { 0 } else { (-exponent + n as i64 - 1) / n as i64 };
+		let radicand = self.mantissa.abs() * pow10((exponent + extra * n as i64) as u32);
+		let divisor = pow10(extra as u32);
+		
+		let rounding = if self.is_negative() { ctx.rounding.mirrored() } else { ctx.rounding };
+		let magnitude = round_root(&radicand, &divisor, n, rounding);
+		let mantissa = if self.is_negative() { -magnitude } else { magnitude };
+		Ok(Self::new(mantissa, ctx.precision).normalize())
+	}
+	
+	// A nonzero value too small to show at the context precision rounds to zero or to
+	// one unit in the last place, depending on the direction of rounding
+	fn round_tiny(negative: bool, ctx: &Context) -> Self {
+		let away_from_zero = match ctx.rounding {
+			RoundingMode::Up => true,
+			RoundingMode::Ceiling => !negative,
+			RoundingMode::Floor => negative,
+			_ => false,
+		};
+		if !away_from_zero {
+			return Self::new(BigInt::zero(), 0);
+		}
+		let unit = if negative { -BigInt::one() } else { BigInt::one() };
+		Self::new(unit, ctx.precision)
 	}
 	
-	pub fn to_string(&self) -> String {
-		self.to_string_with_limit(25) // Default limit for display
+	pub fn is_zero(&self) -> bool {
+		self.mantissa.is_zero()
 	}
 	
-	pub fn to_string_with_limit(&self, max_chars: usize) -> String {
-		let standard_form = self.to_standard_string();
+	pub fn is_negative(&self) -> bool {
+		self.mantissa.is_negative()
+	}
+	
+	pub fn is_integer(&self) -> bool {
+		self.scale <= 0 || (&self.mantissa % pow10(self.scale as u32)).is_zero()
+	}
+	
+	// Integer part, truncated toward zero
+	fn to_integer(&self) -> BigInt {
+		self.scale_to(0, RoundingMode::Down).mantissa
+	}
+	
+	pub fn to_u32(&self) -> Option<u32> {
+		if !self.is_integer() {
+			return None;
+		}
+		self.to_integer().to_u32()
+	}
+	
+	// Approximate decimal logarithm of |x|, usable for magnitude estimates
+	fn log10_estimate(&self) -> f64 {
+		let digits = self.mantissa.to_string();
+		let digits = digits.trim_start_matches('-');
+		let leading: f64 = digits[..digits.len().min(17)].parse().unwrap_or(0.0);
+		let exponent = digits.len().saturating_sub(17) as f64 - self.scale as f64;
+		leading.log10() + exponent
+	}
+	
+	fn to_f64(&self) -> f64 {
+		let magnitude = 10f64.powf(self.log10_estimate());
+		if self.is_negative() { -magnitude } else { magnitude }
+	}
+	
+	pub fn to_display_string(&self, ctx: &Context) -> String {
+		// Huge numbers like 100000! are far too long without formatting every digit first
+		let shortest = digit_count(&self.mantissa) + (-self.scale).max(0) as usize;
+		if shortest <= ctx.display_limit {
+			let standard_form = self.to_standard_string();
+			if standard_form.len() <= ctx.display_limit {
+				return standard_form;
+			}
+		}
 		
-		if standard_form.len() <= max_chars {
-			return standard_form;
+		if let Some(fitted) = self.to_fitted_string(ctx) {
+			return fitted;
+		}
+		self.to_scientific_notation(ctx)
+	}
+	
+	// Plain form with the digits after the point rounded to fit the display, or None when
+	// the exponent is out of its range: the integer part is too wide, or so many zeros follow
+	// the point that fewer significant digits would show than in scientific notation
+	fn to_fitted_string(&self, ctx: &Context) -> Option<String> {
+		let digits = digit_count(&self.mantissa) as i64;
+		let exponent = digits - 1 - self.scale as i64;
+		let sign = if self.is_negative() { 1 } else { 0 };
+		let integer_digits = exponent.max(0) + 1;
+		let width = ctx.display_limit as i64 - sign - integer_digits;
+		if width < 0 {
+			return None;
 		}
 		
-		// Convert to scientific notation if too long
-		self.to_scientific_notation()
+		let places = (width - 1).max(0);
+		let shown = places + exponent + 1;
+		if shown < digits.min(ctx.scientific_digits as i64 + 1) {
+			return None;
+		}
+		let fitted = self.scale_to(places as i32, ctx.rounding).normalize().to_standard_string();
+		(fitted.len() <= ctx.display_limit).then_some(fitted)
 	}
 	
 	fn to_standard_string(&self) -> String {
@@ -244,44 +707,66 @@ impl BigNumber {
 		}
 	}
 	
-	fn to_scientific_notation(&self) -> String {
-		if self.mantissa.is_zero() {
-			return "0".to_string();
+	// Renders an exact fraction as a decimal, an improper fraction (7/4) or a mixed number (1 3/4).
+	// Decimals that repeat show the repetend in parentheses when it fits the display.
+	pub fn fraction_to_string(value: &Rational, ctx: &Context) -> String {
+		if value.is_integer() {
+			return value.to_decimal(ctx).to_display_string(ctx);
+		}
+		if ctx.fraction_style == FractionStyle::Decimal {
+			return repeating_expansion(value, 10, ctx.display_limit)
+				.unwrap_or_else(|| value.to_decimal(ctx).to_display_string(ctx));
 		}
 		
-		let mantissa_str = self.mantissa.to_string();
-		let is_negative = mantissa_str.starts_with('-');
-		let abs_str = if is_negative { &mantissa_str[1..] } else { &mantissa_str };
+		let numerator = value.numerator();
+		let denominator = value.denominator();
+		let whole = numerator / denominator;
+		if ctx.fraction_style == FractionStyle::Improper || whole.is_zero() {
+			return format!("{}/{}", numerator, denominator);
+		}
 		
-		if abs_str.is_empty() {
+		// The sign goes in front of the whole part: -7/4 is "-1 3/4"
+		let remainder = (numerator % denominator).abs();
+		format!("{} {}/{}", whole, remainder, denominator)
+	}
+	
+	fn to_scientific_notation(&self, ctx: &Context) -> String {
+		if self.mantissa.is_zero() {
 			return "0".to_string();
 		}
 		
-		// Find the position of the most significant digit
-		let significant_digits: Vec<char> = abs_str.chars().collect();
+		let digits = ctx.scientific_digits;
+		let length = digit_count(&self.mantissa);
 		
 		// Calculate the exponent
-		let exponent = (significant_digits.len() as i32) - 1 - self.scale;
+		let mut exponent = (length as i64) - 1 - self.scale as i64;
 		
-		// Format the mantissa (keep first digit, then decimal point, then up to 10 more digits)
+		// Round away the digits that will not be shown; a carry (9.99 -> 10.0) bumps the exponent
+		let abs_str = if length > digits + 1 {
+			let dropped = (length - digits - 1) as u32;
+			let rounded = divide_rounded(&self.mantissa, &pow10(dropped), ctx.rounding).abs().to_string();
+			if rounded.len() > digits + 1 {
+				exponent += 1;
+			}
+			rounded
+		} else {
+			self.mantissa.abs().to_string()
+		};
+		
+		// Format the mantissa (keep first digit, then decimal point, then the remaining digits)
 		let mut formatted_mantissa = String::new();
-		if is_negative {
+		if self.is_negative() {
 			formatted_mantissa.push('-');
 		}
 		
-		formatted_mantissa.push(significant_digits[0]);
+		formatted_mantissa.push_str(&abs_str[..1]);
 		
-		if significant_digits.len() > 1 {
+		let remaining_digits: String = abs_str[1..].chars().take(digits).collect();
+		// Remove trailing zeros in scientific notation
+		let trimmed = remaining_digits.trim_end_matches('0');
+		if !trimmed.is_empty() {
 			formatted_mantissa.push('.');
-			// Take up to 10 digits after the decimal point for scientific notation
-			let remaining_digits: String = significant_digits[1..].iter()
-				.take(10)
-				.collect();
-			// Remove trailing zeros in scientific notation
-			let trimmed = remaining_digits.trim_end_matches('0');
-			if !trimmed.is_empty() {
-				formatted_mantissa.push_str(trimmed);
-			}
+			formatted_mantissa.push_str(trimmed);
 		}
 		
 		format!("{}e{}", formatted_mantissa, exponent)
@@ -289,7 +774,344 @@ impl BigNumber {
 }
 
 
+// Expansion of a fraction in base `radix` with its repetend in parentheses, like "0.1(6)" or
+// "0b0.0(0011)", or None when the expansion terminates or would be longer than `max_len` characters
+fn repeating_expansion(value: &Rational, radix: u32, max_len: usize) -> Option<String> {
+	let denominator = value.denominator();
+	
+	// The repetend starts after one digit for each time the denominator shares a factor with
+	// the radix: 1/12 = 0.08(3) in decimal, since 12 = 2 * 2 * 3. Each prime of the radix is
+	// divided out as a whole power, and a preperiod that cannot fit stops the search early.
+	let mut coprime = denominator.clone();
+	let mut preperiod = 0;
+	let mut rest = radix;
+	for p in 2..=radix {
+		let mut multiplicity = 0;
+		while rest.is_multiple_of(p) {
+			rest /= p;
+			multiplicity += 1;
+		}
+		if multiplicity == 0 {
+			continue;
+		}
+		let count = if p == 2 {
+			let zeros = coprime.trailing_zeros().unwrap_or(0);
+			coprime >>= zeros;
+			zeros as usize
+		} else {
+			// Powers of p^(2^k) strip long runs of factors in few divisions
+			let mut count = 0;
+			let mut powers = vec![(BigInt::from(p), 1)];
+			while let Some((power, exponent)) = powers.last()
+				&& power.bits() * 2 <= coprime.bits() {
+				powers.push((power * power, exponent * 2));
+			}
+			for (power, exponent) in powers.iter().rev() {
+				while (&coprime % power).is_zero() {
+					coprime /= power;
+					count += exponent;
+				}
+			}
+			count
+		};
+		preperiod = preperiod.max(count.div_ceil(multiplicity));
+		if preperiod >= max_len {
+			return None;
+		}
+	}
+	if coprime.is_one() {
+		return None;
+	}
+	
+	let numerator = value.numerator().abs();
+	let sign = if value.numerator().is_negative() { "-" } else { "" };
+	let whole = (&numerator / denominator).to_str_radix(radix).to_uppercase();
+	let mut expansion = format!("{}{}{}.", sign, radix_prefix(radix), whole);
+	let mut remainder = numerator % denominator;
+	let next_digit = |remainder: &mut BigInt, expansion: &mut String| {
+		*remainder *= radix;
+		let digit = (&*remainder / denominator).to_u32().unwrap_or(0);
+		expansion.push(std::char::from_digit(digit, radix).unwrap_or('?').to_ascii_uppercase());
+		*remainder %= denominator;
+		expansion.len() < max_len
+	};
+	
+	for _ in 0..preperiod {
+		if !next_digit(&mut remainder, &mut expansion) {
+			return None;
+		}
+	}
+	
+	// From here on the remainders cycle; the period ends when the first one comes back
+	expansion.push('(');
+	let start = remainder.clone();
+	loop {
+		if !next_digit(&mut remainder, &mut expansion) {
+			return None;
+		}
+		if remainder == start {
+			break;
+		}
+	}
+	expansion.push(')');
+	Some(expansion)
+}
+
+// Integer division of n by d, rounded according to `rounding`
+fn divide_rounded(n: &BigInt, d: &BigInt, rounding: RoundingMode) -> BigInt {
+	let quotient = n / d;
+	let remainder = n % d;
+	if remainder.is_zero() {
+		return quotient;
+	}
+	
+	// Direction away from zero for the exact quotient
+	let step = if n.is_negative() != d.is_negative() { -BigInt::one() } else { BigInt::one() };
+	let twice_remainder = remainder.abs() * 2;
+	let divisor = d.abs();
+	
+	let round_away = match rounding {
+		RoundingMode::Down => false,
+		RoundingMode::Up => true,
+		RoundingMode::Floor => step.is_negative(),
+		RoundingMode::Ceiling => step.is_positive(),
+		RoundingMode::HalfUp => twice_remainder >= divisor,
+		RoundingMode::HalfDown => twice_remainder > divisor,
+		RoundingMode::HalfEven => {
+			twice_remainder > divisor
+				|| (twice_remainder == divisor && !(&quotient & BigInt::one()).is_zero())
+		}
+	};
+	
+	if round_away { quotient + step } else { quotient }
+}
+
+// floor(a^(1/n)) for a >= 0 by Newton iteration, starting above the root
+fn integer_root(a: &BigInt, n: u32) -> BigInt {
+	if a.is_zero() {
+		return BigInt::zero();
+	}
+	
+	let mut x = BigInt::one() << a.bits().div_ceil(n as u64);
+	loop {
+		let next = (&x * (n - 1) + a / x.pow(n - 1)) / n;
+		if next >= x {
+			return x;
+		}
+		x = next;
+	}
+}
+
+// a^(1/n) / divisor rounded to an integer, with a >= 0 and divisor > 0
+fn round_root(a: &BigInt, divisor: &BigInt, n: u32, rounding: RoundingMode) -> BigInt {
+	let floor = integer_root(a, n) / divisor;
+	if (&floor * divisor).pow(n) == *a {
+		return floor;
+	}
+	
+	// Not exact at this precision, but it can still sit exactly halfway when the
+	// divisor is involved: compare 2^n * a with ((2*floor + 1) * divisor)^n
+	let half_power = ((&floor * 2u32 + 1u32) * divisor).pow(n);
+	let scaled = a << n;
+	let round_up = match rounding {
+		RoundingMode::Down | RoundingMode::Floor => false,
+		RoundingMode::Up | RoundingMode::Ceiling => true,
+		RoundingMode::HalfUp => scaled >= half_power,
+		RoundingMode::HalfDown => scaled > half_power,
+		RoundingMode::HalfEven => {
+			scaled > half_power || (scaled == half_power && !(&floor & BigInt::one()).is_zero())
+		}
+	};
+	
+	if round_up { floor + 1 } else { floor }
+}
+
+// Largest number of digits a power or exponential may produce
+const MAX_RESULT_DIGITS: f64 = 1_000_000.0;
+
+fn check_result_digits(digits: f64) -> Result<(), String> {
+	if digits.is_finite() && digits <= MAX_RESULT_DIGITS {
+		return Ok(());
+	}
+	if digits < 1e15 {
+		Err(format!("Result too large: about {:.0} digits (limit is {:.0})", digits, MAX_RESULT_DIGITS))
+	} else {
+		Err(format!("Result too large (limit is {:.0} digits)", MAX_RESULT_DIGITS))
+	}
+}
+
+fn pow10(exp: u32) -> BigInt {
+	BigInt::from(10).pow(exp)
+}
+
+// Number of decimal digits in |n|, without formatting it
+fn digit_count(n: &BigInt) -> usize {
+	if n.is_zero() {
+		return 1;
+	}
+	// 2^(bits - 1) <= |n| < 2^bits, so the estimate is exact or one short
+	let estimate = ((n.bits() - 1) as f64 * std::f64::consts::LOG10_2) as usize + 1;
+	if n.magnitude() >= pow10(estimate as u32).magnitude() { estimate + 1 } else { estimate }
+}
+
+// atanh(z) = z + z^3/3 + z^5/5 + ... in binary fixed point with `bits` fractional bits
+fn atanh_fixed(z: &BigInt, bits: u64) -> BigInt {
+	let z_squared = (z * z) >> bits;
+	let mut sum = BigInt::zero();
+	let mut power = z.clone();
+	let mut n = 1u32;
+	loop {
+		let term = &power / n;
+		if term.is_zero() {
+			break;
+		}
+		sum += term;
+		power = (power * &z_squared) >> bits;
+		n += 2;
+	}
+	sum
+}
+
+// atanh(1/n) for a small integer n; dividing by n^2 avoids any big multiplication
+fn atanh_inverse_fixed(n: u64, bits: u64) -> BigInt {
+	let n_squared = n * n;
+	let mut sum = BigInt::zero();
+	let mut power = (BigInt::one() << bits) / n;
+	let mut k = 1u64;
+	loop {
+		let term = &power / k;
+		if term.is_zero() {
+			break;
+		}
+		sum += term;
+		power /= n_squared;
+		k += 2;
+	}
+	sum
+}
+
+// ln 2 = 18 atanh(1/26) - 2 atanh(1/4801) + 8 atanh(1/8749)
+fn ln2_fixed(bits: u64) -> BigInt {
+	atanh_inverse_fixed(26, bits) * 18 - atanh_inverse_fixed(4801, bits) * 2 + atanh_inverse_fixed(8749, bits) * 8
+}
+
+// ln 10 = 3 ln 2 + ln(5/4), and ln(5/4) = 2 atanh(1/9)
+fn ln10_fixed(bits: u64) -> BigInt {
+	ln2_fixed(bits) * 3 + atanh_inverse_fixed(9, bits) * 2
+}
+
+fn decimal_to_bits(digits: f64) -> u64 {
+	(digits.max(0.0) * std::f64::consts::LOG2_10).ceil() as u64
+}
+
+impl fmt::Display for BigNumber {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		write!(f, "{}", self.to_display_string(&Context::default()))
+	}
+}
 
 pub fn parse_number(s: &str) -> Result<BigNumber, String> {
 	BigNumber::from_str(s)
 }
+
+pub fn parse_repeating(s: &str) -> Result<Rational, String> {
+	BigNumber::from_repeating(s)
+}
+
+pub fn parse_radix(s: &str, radix: u32) -> Result<Rational, String> {
+	BigNumber::from_radix(s, radix)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::calculate;
+
+	#[test]
+	fn rounding_modes_to_whole_numbers() {
+		let values = ["2.5", "-2.5", "2.4", "-2.6", "3.5"];
+		let expected = [
+			(RoundingMode::HalfEven, ["2", "-2", "2", "-3", "4"]),
+			(RoundingMode::HalfUp, ["3", "-3", "2", "-3", "4"]),
+			(RoundingMode::HalfDown, ["2", "-2", "2", "-3", "3"]),
+			(RoundingMode::Floor, ["2", "-3", "2", "-3", "3"]),
+			(RoundingMode::Ceiling, ["3", "-2", "3", "-2", "4"]),
+			(RoundingMode::Up, ["3", "-3", "3", "-3", "4"]),
+			(RoundingMode::Down, ["2", "-2", "2", "-2", "3"]),
+		];
+		for (mode, results) in expected {
+			for (value, result) in values.iter().zip(results) {
+				let rounded = parse_number(value).unwrap().scale_to(0, mode);
+				assert_eq!(rounded.to_standard_string(), result, "{} with {:?}", value, mode);
+			}
+		}
+	}
+
+	#[test]
+	fn division_rounds_in_the_context_mode() {
+		let ctx = Context { precision: 3, ..Context::default() };
+		let two = parse_number("2").unwrap();
+		let three = parse_number("3").unwrap();
+		let divide = |a: &BigNumber, rounding| a.divide(&three, &ctx.with_rounding(rounding)).unwrap().to_standard_string();
+		assert_eq!(divide(&two, RoundingMode::Floor), "0.666");
+		assert_eq!(divide(&two, RoundingMode::Ceiling), "0.667");
+		assert_eq!(divide(&two.negate(), RoundingMode::Floor), "-0.667");
+		assert_eq!(divide(&two.negate(), RoundingMode::Down), "-0.666");
+	}
+
+	#[test]
+	fn long_results_are_rounded_to_the_display_before_going_scientific() {
+		let precise = Context { precision: 50, ..Context::default() };
+		assert_eq!(calculate("pi", &Context::default()), "3.141592653589793");
+		assert_eq!(calculate("pi", &precise), "3.14159265358979323846264");
+		assert_eq!(calculate("-pi", &precise), "-3.1415926535897932384626");
+		let wide = Context { display_limit: 60, ..precise.clone() };
+		assert_eq!(calculate("pi", &wide), "3.14159265358979323846264338327950288419716939937511");
+		assert_eq!(calculate("sqrt(2)^2", &Context::default()), "2");
+		assert_eq!(calculate("sqrt(2)^2", &precise), "2");
+		assert_eq!(calculate("2^100", &Context::default()), "1.2676506002e30");
+		assert_eq!(calculate("pi / 10^20", &precise), "3.1415926536e-20");
+	}
+
+	#[test]
+	fn integer_powers_of_decimals_round_to_the_precision() {
+		assert_eq!(calculate("sqrt(2)^2", &Context::default()), "2");
+		let ctx = Context { precision: 2, ..Context::default() };
+		let base = parse_number("1.5").unwrap();
+		let three = parse_number("3").unwrap();
+		assert_eq!(base.power(&three, &ctx).unwrap().to_standard_string(), "3.38");
+		let floor = ctx.with_rounding(RoundingMode::Floor);
+		assert_eq!(base.power(&three, &floor).unwrap().to_standard_string(), "3.37");
+		assert_eq!(parse_number("12").unwrap().power(&three, &ctx).unwrap().to_standard_string(), "1728");
+	}
+
+	#[test]
+	fn repeating_decimal_expansions() {
+		let third = Rational::new(BigInt::from(1), BigInt::from(3)).unwrap();
+		assert_eq!(repeating_expansion(&third, 10, 25).as_deref(), Some("0.(3)"));
+		let seventh = Rational::new(BigInt::from(-1), BigInt::from(7)).unwrap();
+		assert_eq!(repeating_expansion(&seventh, 10, 25).as_deref(), Some("-0.(142857)"));
+		let twelfth = Rational::new(BigInt::from(1), BigInt::from(12)).unwrap();
+		assert_eq!(repeating_expansion(&twelfth, 10, 25).as_deref(), Some("0.08(3)"));
+	}
+
+	#[test]
+	fn long_preperiods_give_up_without_dividing_digit_by_digit() {
+		let tiny = Rational::new(BigInt::from(1), pow10(400_000)).unwrap();
+		assert_eq!(repeating_expansion(&tiny, 10, 25), None);
+		let tiny = Rational::new(BigInt::from(1), BigInt::from(3) << 100_000).unwrap();
+		assert_eq!(repeating_expansion(&tiny, 10, 25), None);
+		let tiny = Rational::new(BigInt::from(1), BigInt::from(3) * BigInt::from(5).pow(100_000)).unwrap();
+		assert_eq!(repeating_expansion(&tiny, 10, 25), None);
+		let fits = Rational::new(BigInt::from(1), BigInt::from(3) * BigInt::from(5).pow(20)).unwrap();
+		assert_eq!(repeating_expansion(&fits, 10, 30).as_deref(), Some("0.00000000000000349525(3)"));
+	}
+
+	#[test]
+	fn repeating_expansions_in_any_base() {
+		let third = Rational::new(BigInt::from(1), BigInt::from(3)).unwrap();
+		assert_eq!(repeating_expansion(&third, 2, 25).as_deref(), Some("0b0.(01)"));
+		let tenth = parse_number("0.1").unwrap().to_rational();
+		assert_eq!(repeating_expansion(&tenth, 2, 25).as_deref(), Some("0b0.0(0011)"));
+	}
+}
@@ -0,0 +1,200 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::f64::consts::LOG10_2;
+
+use super::{BigNumber, check_result_digits};
+use crate::context::Context;
+
+// Whether a result had to be cut down to fit the word: carry when it does not fit as an
+// unsigned number, overflow when it does not fit as a signed one. Unlike processor flags
+// they only describe the final result, since the arithmetic before it is exact: in an
+// 8-bit word (200 + 100) - 100 is 200 and sets neither.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WordFlags {
+	pub carry: bool,
+	pub overflow: bool,
+}
+
+// Bitwise operations on integers as two's-complement words of the context's size. Operands
+// are wrapped into the word first. Shifting left gives the exact product, so that programmer
+// mode can tell when the bits shifted out of the word.
+impl BigNumber {
+	pub fn bit_and(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		let (a, b) = (self.word_argument("and", ctx)?, other.word_argument("and", ctx)?);
+		Ok(Self::new(a & b, 0))
+	}
+
+	pub fn bit_or(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		let (a, b) = (self.word_argument("or", ctx)?, other.word_argument("or", ctx)?);
+		Ok(Self::new(a | b, 0))
+	}
+
+	pub fn bit_xor(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		let (a, b) = (self.word_argument("xor", ctx)?, other.word_argument("xor", ctx)?);
+		Ok(Self::new(a ^ b, 0))
+	}
+
+	// Every bit flipped; without a word size that is -x - 1
+	pub fn bit_not(&self, ctx: &Context) -> Result<Self, String> {
+		let x = self.word_argument("not", ctx)?;
+		Ok(Self::new(wrap(&(-x - 1), ctx), 0))
+	}
+
+	pub fn shift_left(&self, amount: &Self, ctx: &Context) -> Result<Self, String> {
+		let x = self.word_argument("<<", ctx)?;
+		let amount = amount.shift_amount("<<")?;
+		if x.is_zero() {
+			return Ok(Self::new(x, 0));
+		}
+		check_result_digits(x.bits().saturating_add(amount) as f64 * LOG10_2)?;
+		Ok(Self::new(x << amount, 0))
+	}
+
+	// Arithmetic shift for signed words, logical for unsigned ones, which have no sign bit
+	pub fn shift_right(&self, amount: &Self, ctx: &Context) -> Result<Self, String> {
+		let x = self.word_argument(">>", ctx)?;
+		let amount = amount.shift_amount(">>")?;
+		Ok(Self::new(x >> amount, 0))
+	}
+
+	pub fn rotate_left(&self, amount: &Self, ctx: &Context) -> Result<Self, String> {
+		self.rotate("rol", amount, false, ctx)
+	}
+
+	pub fn rotate_right(&self, amount: &Self, ctx: &Context) -> Result<Self, String> {
+		self.rotate("ror", amount, true, ctx)
+	}
+
+	// The integer wrapped into the word, with flags for what the wrapping lost
+	pub fn to_word(&self, ctx: &Context) -> Result<(Self, WordFlags), String> {
+		let x = self.integer_argument("Programmer mode")?;
+		let Some(bits) = ctx.word_size.bits() else {
+			return Ok((self.clone(), WordFlags::default()));
+		};
+		let limit = BigInt::one() << bits;
+		let half = BigInt::one() << (bits - 1);
+		let flags = WordFlags {
+			carry: x.is_negative() || x >= limit,
+			overflow: x < -&half || x >= half,
+		};
+		Ok((Self::new(wrap(&x, ctx), 0), flags))
+	}
+
+	// Bits shown for the value: the word size, or for unbounded integers enough whole bytes
+	// to hold the value, with room for a sign bit when it is negative
+	pub fn word_width(&self, ctx: &Context) -> Result<u32, String> {
+		let x = self.integer_argument("Programmer mode")?;
+		if let Some(bits) = ctx.word_size.bits() {
+			return Ok(bits);
+		}
+		let needed = if x.is_negative() { (-x - 1u32).bits() + 1 } else { x.bits() };
+		Ok(needed.max(1).div_ceil(8) as u32 * 8)
+	}
+
+	// Digits of the value's bit pattern in a word of word_width() bits, so that -1 in
+	// an 8-bit word is FF in hexadecimal
+	pub fn word_digits(&self, radix: u32, ctx: &Context) -> Result<String, String> {
+		let x = self.integer_argument("Programmer mode")?;
+		let pattern: BigInt = x & ((BigInt::one() << self.word_width(ctx)?) - 1);
+		Ok(pattern.to_str_radix(radix).to_uppercase())
+	}
+
+	// The value with one bit of its pattern flipped, read back as a word
+	pub fn toggle_bit(&self, bit: u32, ctx: &Context) -> Result<Self, String> {
+		let x = self.integer_argument("Programmer mode")?;
+		Ok(Self::new(wrap(&(x ^ (BigInt::one() << bit)), ctx), 0))
+	}
+
+	fn rotate(&self, name: &str, amount: &Self, right: bool, ctx: &Context) -> Result<Self, String> {
+		let Some(bits) = ctx.word_size.bits() else {
+			return Err(format!("{}() needs a fixed word size to rotate within", name));
+		};
+		let x = self.word_argument(name, ctx)?;
+		let amount = amount.shift_amount(name)? % bits as u64;
+		let amount = if right { (bits as u64 - amount) % bits as u64 } else { amount };
+		let mask = (BigInt::one() << bits) - 1;
+		let pattern = x & &mask;
+		let rotated = ((&pattern << amount) | (&pattern >> (bits as u64 - amount))) & mask;
+		Ok(Self::new(wrap(&rotated, ctx), 0))
+	}
+
+	fn word_argument(&self, name: &str, ctx: &Context) -> Result<BigInt, String> {
+		if !self.is_integer() {
+			return Err(format!("'{}' needs integers, got {}", name, self.to_standard_string()));
+		}
+		Ok(wrap(&self.to_integer(), ctx))
+	}
+
+	fn shift_amount(&self, name: &str) -> Result<u64, String> {
+		match self.to_integer().to_u64() {
+			Some(amount) if self.is_integer() => Ok(amount),
+			_ => Err(format!("'{}' needs a non-negative whole number of bits, got {}", name, self.to_standard_string())),
+		}
+	}
+}
+
+// x reduced to the word: modulo 2^bits, then read as signed or unsigned
+fn wrap(x: &BigInt, ctx: &Context) -> BigInt {
+	let Some(bits) = ctx.word_size.bits() else {
+		return x.clone();
+	};
+	let pattern: BigInt = x & ((BigInt::one() << bits) - 1);
+	if ctx.signed && pattern.bit(bits as u64 - 1) {
+		pattern - (BigInt::one() << bits)
+	} else {
+		pattern
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::context::WordSize;
+
+	fn integer(n: i64) -> BigNumber {
+		BigNumber::new(BigInt::from(n), 0)
+	}
+
+	fn word(size: WordSize, signed: bool) -> Context {
+		Context { word_size: size, signed, ..Context::default() }
+	}
+
+	#[test]
+	fn huge_shifts_are_errors() {
+		let ctx = Context::default();
+		let amount = BigNumber::new(BigInt::from(u64::MAX), 0);
+		assert!(integer(1).shift_left(&amount, &ctx).is_err());
+		assert_eq!(integer(0).shift_left(&amount, &ctx), Ok(integer(0)));
+	}
+
+	#[test]
+	fn unbounded_words_keep_every_bit() {
+		let big = BigNumber::new(BigInt::one() << 70, 0);
+		assert_eq!(big.bit_or(&integer(0), &word(WordSize::Unbounded, true)), Ok(big.clone()));
+		assert_eq!(big.bit_or(&integer(0), &word(WordSize::Bits64, true)), Ok(integer(0)));
+		assert_eq!(integer(5).bit_not(&word(WordSize::Unbounded, true)), Ok(integer(-6)));
+	}
+
+	#[test]
+	fn words_wrap_and_rotate() {
+		let byte = word(WordSize::Bits8, false);
+		assert_eq!(integer(0).bit_not(&byte), Ok(integer(255)));
+		assert_eq!(integer(0).bit_not(&word(WordSize::Bits8, true)), Ok(integer(-1)));
+		assert_eq!(integer(0b1000_0001).rotate_left(&integer(1), &byte), Ok(integer(0b0000_0011)));
+		assert_eq!(integer(0b1000_0001).rotate_right(&integer(1), &byte), Ok(integer(0b1100_0000)));
+		assert!(integer(1).rotate_left(&integer(1), &word(WordSize::Unbounded, true)).is_err());
+		assert_eq!(integer(-16).shift_right(&integer(2), &word(WordSize::Bits8, true)), Ok(integer(-4)));
+	}
+
+	#[test]
+	fn flags_describe_the_final_result() {
+		let byte = word(WordSize::Bits8, false);
+		let (value, flags) = integer(300).to_word(&byte).unwrap();
+		assert_eq!(value, integer(44));
+		assert_eq!(flags, WordFlags { carry: true, overflow: true });
+		let (_, flags) = integer(200).to_word(&byte).unwrap();
+		assert_eq!(flags, WordFlags { carry: false, overflow: true });
+		let (_, flags) = integer(-1).to_word(&byte).unwrap();
+		assert_eq!(flags, WordFlags { carry: true, overflow: false });
+	}
+}
@@ -0,0 +1,322 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::f64::consts::LN_10;
+
+use super::{BigNumber, check_result_digits};
+use super::primes::primes_up_to;
+
+// Stirling numbers are built row by row in O(n k) steps, so n is kept small
+const MAX_STIRLING_N: u64 = 2000;
+
+// Binomials sieve the primes up to n, which takes about n bytes
+const MAX_SIEVED_BINOMIAL_N: u64 = 1 << 24;
+
+// Exact combinatorial functions of non-negative integers
+impl BigNumber {
+	pub fn factorial(&self) -> Result<Self, String> {
+		let n = self.natural("factorial")?;
+		check_result_digits(ln_factorial(&n) / LN_10)?;
+		Ok(Self::new(factorial(n.to_u64().ok_or("Factorial argument too large")?), 0))
+	}
+
+	// n!! = n (n - 2) (n - 4) ..., down to 2 or 1
+	pub fn double_factorial(&self) -> Result<Self, String> {
+		let n = self.natural("double_factorial")?;
+		check_result_digits(ln_factorial(&n) / 2.0 / LN_10)?;
+		let n = n.to_u64().ok_or("Double factorial argument too large")?;
+		let k = n / 2;
+		let value = if n % 2 == 0 {
+			// (2k)!! = 2^k k!
+			factorial(k) << k
+		} else {
+			// (2k + 1)!! = 1 * 3 * 5 ... (2k + 1)
+			let odd: Vec<BigInt> = (1..=n).step_by(2).map(BigInt::from).collect();
+			product(&odd)
+		};
+		Ok(Self::new(value, 0))
+	}
+
+	// Ways to choose k of n items, C(n, k)
+	pub fn binomial(&self, k: &Self) -> Result<Self, String> {
+		let n = self.natural("nCr")?;
+		let k = k.natural("nCr")?;
+		Ok(Self::new(binomial(&n, &k)?, 0))
+	}
+
+	// Ordered selections of k of n items, n! / (n - k)!
+	pub fn permutations(&self, k: &Self) -> Result<Self, String> {
+		let n = self.natural("nPr")?;
+		let k = k.natural("nPr")?;
+		if k > n {
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		// The empty selection, which would make the size estimate 0 * log10(0)
+		if k.is_zero() {
+			return Ok(Self::new(BigInt::one(), 0));
+		}
+		check_result_digits(k.to_f64().unwrap_or(f64::INFINITY) * n.to_f64().unwrap_or(f64::INFINITY).log10())?;
+		let count = k.to_u64().ok_or("nPr() argument too large")?;
+		Ok(Self::new(product_range(&(&n - &k + 1), count), 0))
+	}
+
+	// (k1 + k2 + ...)! / (k1! k2! ...), as a product of binomial coefficients
+	pub fn multinomial(parts: &[Self]) -> Result<Self, String> {
+		let mut total = BigInt::zero();
+		let mut value = BigInt::one();
+		let mut digits = 0.0;
+		for part in parts {
+			let k = part.natural("multinomial")?;
+			total += &k;
+			digits += binomial_digits(&total, &k);
+			check_result_digits(digits)?;
+			value *= binomial(&total, &k)?;
+		}
+		Ok(Self::new(value, 0))
+	}
+
+	// C(2n, n) / (n + 1)
+	pub fn catalan(&self) -> Result<Self, String> {
+		let n = self.natural("catalan")?;
+		let central = binomial(&(&n * 2), &n)?;
+		Ok(Self::new(central / (n + 1), 0))
+	}
+
+	// Unsigned Stirling numbers of the first kind: permutations of n items with k cycles
+	pub fn stirling1(&self, k: &Self) -> Result<Self, String> {
+		let (n, k) = Self::stirling_arguments("stirling1", self, k)?;
+		Ok(Self::new(stirling(n, k, true), 0))
+	}
+
+	// Stirling numbers of the second kind: partitions of n items into k non-empty sets
+	pub fn stirling2(&self, k: &Self) -> Result<Self, String> {
+		let (n, k) = Self::stirling_arguments("stirling2", self, k)?;
+		Ok(Self::new(stirling(n, k, false), 0))
+	}
+
+	fn stirling_arguments(name: &str, n: &Self, k: &Self) -> Result<(u64, u64), String> {
+		let n = n.natural(name)?;
+		let k = k.natural(name)?;
+		match n.to_u64() {
+			Some(n) if n <= MAX_STIRLING_N => Ok((n, k.to_u64().unwrap_or(u64::MAX))),
+			_ => Err(format!("{}() is limited to n up to {}", name, MAX_STIRLING_N)),
+		}
+	}
+
+	fn natural(&self, name: &str) -> Result<BigInt, String> {
+		if !self.is_integer() || self.is_negative() {
+			return Err(format!("{}() needs non-negative integers, got {}", name, self.to_standard_string()));
+		}
+		Ok(self.to_integer())
+	}
+}
+
+// n! by the prime-swing method: n! = ((n/2)!)^2 * swing(n), where the swinging factorial
+// is assembled from its prime factorization
+fn factorial(n: u64) -> BigInt {
+	// Below this the plain product is faster than sieving
+	if n < 32 {
+		return (2..=n).map(BigInt::from).product();
+	}
+	let primes = primes_up_to(n);
+	swing_factorial(n, &primes)
+}
+
+fn swing_factorial(n: u64, primes: &[u64]) -> BigInt {
+	if n < 2 {
+		return BigInt::one();
+	}
+	let half = swing_factorial(n / 2, primes);
+	&half * &half * swing(n, primes)
+}
+
+// n! / ((n/2)!)^2. The exponent of each prime counts the odd quotients n / p^i, and
+// every prime power in it is at most n.
+fn swing(n: u64, primes: &[u64]) -> BigInt {
+	let mut factors = Vec::new();
+	for &p in primes.iter().take_while(|&&p| p <= n) {
+		let mut factor = 1;
+		let mut quotient = n;
+		while quotient >= p {
+			quotient /= p;
+			if quotient % 2 == 1 {
+				factor *= p;
+			}
+		}
+		if factor > 1 {
+			factors.push(BigInt::from(factor));
+		}
+	}
+	product(&factors)
+}
+
+// C(n, k), built from its prime factorization so no huge division is needed
+fn binomial(n: &BigInt, k: &BigInt) -> Result<BigInt, String> {
+	if k > n {
+		return Ok(BigInt::zero());
+	}
+	let k = k.min(&(n - k)).clone();
+	check_result_digits(binomial_digits(n, &k))?;
+	let count = k.to_u64().ok_or("nCr() argument too large")?;
+	match n.to_u64() {
+		Some(n) if n <= MAX_SIEVED_BINOMIAL_N => Ok(kummer_binomial(n, count)),
+		_ => Ok(falling_binomial(n, count)),
+	}
+}
+
+// By Kummer's theorem the exponent of p in C(n, k) is the number of carries when adding
+// k and n - k in base p, which Legendre's formula counts as the sum of
+// n/p^i - k/p^i - (n - k)/p^i. As in swing(), every prime power in it is at most n.
+fn kummer_binomial(n: u64, k: u64) -> BigInt {
+	let mut factors = Vec::new();
+	for p in primes_up_to(n) {
+		let mut factor = 1;
+		let (mut whole, mut chosen, mut rest) = (n, k, n - k);
+		while whole >= p {
+			whole /= p;
+			chosen /= p;
+			rest /= p;
+			for _ in 0..whole - chosen - rest {
+				factor *= p;
+			}
+		}
+		if factor > 1 {
+			factors.push(BigInt::from(factor));
+		}
+	}
+	product(&factors)
+}
+
+// C(n, k) for n too large to sieve: the falling product n (n - 1) ... (n - k + 1) with the
+// primes of k! divided out of its terms. By Legendre's formula k! has k/p + k/p^2 + ...
+// factors of p, and the multiples of p among k consecutive terms hold at least as many.
+fn falling_binomial(n: &BigInt, k: u64) -> BigInt {
+	let start = n - k + 1;
+	let mut terms: Vec<BigInt> = (0..k).map(|i| &start + i).collect();
+	for p in primes_up_to(k) {
+		let mut exponent = 0;
+		let mut quotient = k;
+		while quotient >= p {
+			quotient /= p;
+			exponent += quotient;
+		}
+		// Terms from the first multiple of p on, every p-th one
+		let remainder: BigInt = &start % p;
+		let offset = remainder.to_u64().map_or(0, |r| (p - r) % p);
+		for term in terms.iter_mut().skip(offset as usize).step_by(p as usize) {
+			while exponent > 0 && (&*term % p).is_zero() {
+				*term /= p;
+				exponent -= 1;
+			}
+			if exponent == 0 {
+				break;
+			}
+		}
+	}
+	product(&terms)
+}
+
+// Upper bound on the digits of C(n, k), from C(n, k) <= n^k / k!
+fn binomial_digits(n: &BigInt, k: &BigInt) -> f64 {
+	let k = k.min(&(n - k)).clone();
+	if k.is_positive() {
+		(k.to_f64().unwrap_or(f64::INFINITY) * n.to_f64().unwrap_or(f64::INFINITY).ln() - ln_factorial(&k)) / LN_10
+	} else {
+		0.0
+	}
+}
+
+// Stirling's approximation of ln n!, for size estimates
+fn ln_factorial(n: &BigInt) -> f64 {
+	let n = n.to_f64().unwrap_or(f64::INFINITY);
+	if n < 2.0 {
+		return 0.0;
+	}
+	n * n.ln() - n + 0.5 * (2.0 * std::f64::consts::PI * n).ln()
+}
+
+// Builds the row of Stirling numbers for n from the one for n - 1, updating it in place
+// from the right: c(i + 1, j) = i c(i, j) + c(i, j - 1) for the first kind and
+// S(i + 1, j) = j S(i, j) + S(i, j - 1) for the second
+fn stirling(n: u64, k: u64, first_kind: bool) -> BigInt {
+	if k > n {
+		return BigInt::zero();
+	}
+	let k = k as usize;
+	let mut row = vec![BigInt::zero(); k + 1];
+	row[0] = BigInt::one();
+	for i in 0..n {
+		for j in (1..=k.min(i as usize + 1)).rev() {
+			let factor = if first_kind { i } else { j as u64 };
+			row[j] = &row[j] * factor + &row[j - 1];
+		}
+		row[0] = BigInt::zero();
+	}
+	row.swap_remove(k)
+}
+
+// start (start + 1) ... (start + count - 1)
+fn product_range(start: &BigInt, count: u64) -> BigInt {
+	let factors: Vec<BigInt> = (0..count).map(|i| start + i).collect();
+	product(&factors)
+}
+
+// Balanced product tree, so the large multiplications have operands of similar size
+fn product(factors: &[BigInt]) -> BigInt {
+	match factors {
+		[] => BigInt::one(),
+		[factor] => factor.clone(),
+		_ => {
+			let (left, right) = factors.split_at(factors.len() / 2);
+			product(left) * product(right)
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn integer(n: i64) -> BigNumber {
+		BigNumber::new(BigInt::from(n), 0)
+	}
+
+	#[test]
+	fn permutations_and_binomials() {
+		assert_eq!(integer(0).permutations(&integer(0)), Ok(integer(1)));
+		assert_eq!(integer(5).permutations(&integer(0)), Ok(integer(1)));
+		assert_eq!(integer(5).permutations(&integer(2)), Ok(integer(20)));
+		assert_eq!(integer(2).permutations(&integer(3)), Ok(integer(0)));
+		assert_eq!(integer(0).binomial(&integer(0)), Ok(integer(1)));
+		assert_eq!(integer(5).binomial(&integer(2)), Ok(integer(10)));
+	}
+
+	#[test]
+	fn factorials() {
+		assert_eq!(integer(0).factorial(), Ok(integer(1)));
+		assert_eq!(integer(10).factorial(), Ok(integer(3628800)));
+		assert_eq!(integer(7).double_factorial(), Ok(integer(105)));
+		assert!(integer(-1).factorial().is_err());
+	}
+
+	#[test]
+	fn binomials_follow_pascals_rule() {
+		for n in 1..60u64 {
+			for k in 1..n {
+				let sum = kummer_binomial(n - 1, k - 1) + kummer_binomial(n - 1, k);
+				assert_eq!(kummer_binomial(n, k), sum, "C({}, {})", n, k);
+				assert_eq!(falling_binomial(&BigInt::from(n), k), sum, "C({}, {})", n, k);
+			}
+		}
+		let n = BigInt::from(1u64 << 40);
+		assert_eq!(binomial(&n, &BigInt::from(2)), Ok(&n * (&n - 1) / 2));
+	}
+
+	#[test]
+	fn large_binomials_and_double_factorials() {
+		let central = integer(1_000_000).binomial(&integer(500_000)).unwrap();
+		assert_eq!(central.to_standard_string().len(), 301_027);
+		let odd = integer(100_001).double_factorial().unwrap();
+		let even = integer(100_000).double_factorial().unwrap();
+		assert_eq!(odd.multiply(&even), integer(100_001).factorial().unwrap());
+	}
+}
@@ -0,0 +1,293 @@
+use num_bigint::BigInt;
+use num_traits::{One, ToPrimitive, Zero};
+
+use super::{BigNumber, RoundingMode, check_result_digits};
+use crate::context::{AngleUnit, Context};
+
+// Complex number with decimal real and imaginary parts. Sums and products are exact
+// like BigNumber's; everything else is rounded to the context precision.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Complex {
+	re: BigNumber,
+	im: BigNumber,
+}
+
+impl Complex {
+	pub fn new(re: BigNumber, im: BigNumber) -> Self {
+		Self { re, im }
+	}
+
+	pub fn from_real(re: BigNumber) -> Self {
+		Self::new(re, BigNumber::new(BigInt::zero(), 0))
+	}
+
+	pub fn from_imaginary(im: BigNumber) -> Self {
+		Self::new(BigNumber::new(BigInt::zero(), 0), im)
+	}
+
+	pub fn i() -> Self {
+		Self::new(BigNumber::new(BigInt::zero(), 0), BigNumber::new(BigInt::one(), 0))
+	}
+
+	// r(cos theta + i sin theta), with theta in the context's angle unit
+	pub fn from_polar(modulus: &BigNumber, angle: &BigNumber, ctx: &Context) -> Result<Self, String> {
+		let work = Context { angle_unit: ctx.angle_unit, ..working(ctx, modulus.log10_estimate()) };
+		let re = modulus.multiply(&angle.cos(&work)?);
+		let im = modulus.multiply(&angle.sin(&work)?);
+		Ok(Self::new(re, im).round(ctx))
+	}
+
+	pub fn re(&self) -> &BigNumber {
+		&self.re
+	}
+
+	pub fn im(&self) -> &BigNumber {
+		&self.im
+	}
+
+	pub fn is_real(&self) -> bool {
+		self.im.is_zero()
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.re.is_zero() && self.im.is_zero()
+	}
+
+	pub fn negate(&self) -> Self {
+		Self::new(self.re.negate(), self.im.negate())
+	}
+
+	pub fn conjugate(&self) -> Self {
+		Self::new(self.re.clone(), self.im.negate())
+	}
+
+	pub fn add(&self, other: &Self) -> Self {
+		Self::new(self.re.add(&other.re), self.im.add(&other.im))
+	}
+
+	pub fn subtract(&self, other: &Self) -> Self {
+		Self::new(self.re.subtract(&other.re), self.im.subtract(&other.im))
+	}
+
+	pub fn multiply(&self, other: &Self) -> Self {
+		let re = self.re.multiply(&other.re).subtract(&self.im.multiply(&other.im));
+		let im = self.re.multiply(&other.im).add(&self.im.multiply(&other.re));
+		Self::new(re, im)
+	}
+
+	// (a + bi) / (c + di) = ((ac + bd) + (bc - ad)i) / (c^2 + d^2)
+	pub fn divide(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		if other.is_zero() {
+			return Err("Division by zero".to_string());
+		}
+		let numerator = self.multiply(&other.conjugate());
+		let norm = other.norm();
+		Ok(Self::new(numerator.re.divide(&norm, ctx)?, numerator.im.divide(&norm, ctx)?))
+	}
+
+	// Modulus |z|
+	pub fn abs(&self, ctx: &Context) -> Result<BigNumber, String> {
+		self.norm().sqrt(ctx)
+	}
+
+	// Argument in (-pi, pi], in the context's angle unit
+	pub fn arg(&self, ctx: &Context) -> Result<BigNumber, String> {
+		if self.is_zero() {
+			return Err("The argument of zero is undefined".to_string());
+		}
+		self.im.atan2(&self.re, ctx)
+	}
+
+	// Principal square root, with a non-negative real part
+	pub fn sqrt(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Ok(self.clone());
+		}
+
+		// The part computed from (r +- a) / 2 avoids cancellation; the other follows from b
+		let work = working(ctx, 0.0);
+		let modulus = self.abs(&work)?;
+		let half = BigNumber::new(BigInt::from(5), 1);
+		let two = BigNumber::new(BigInt::from(2), 0);
+		let root = if self.re.is_negative() {
+			let im = modulus.subtract(&self.re).multiply(&half).sqrt(&work)?;
+			let im = if self.im.is_negative() { im.negate() } else { im };
+			Self::new(self.im.divide(&im.multiply(&two), &work)?, im)
+		} else {
+			let re = modulus.add(&self.re).multiply(&half).sqrt(&work)?;
+			let im = self.im.divide(&re.multiply(&two), &work)?;
+			Self::new(re, im)
+		};
+		Ok(root.round(ctx))
+	}
+
+	// e^(a + bi) = e^a (cos b + i sin b)
+	pub fn exp(&self, ctx: &Context) -> Result<Self, String> {
+		// cos b and sin b are scaled by e^a, so they need its integer digits as well
+		let work = working(ctx, self.re.to_f64() * std::f64::consts::LOG10_E);
+		let scale = self.re.exp(&work)?;
+		let re = scale.multiply(&self.im.cos(&work)?);
+		let im = scale.multiply(&self.im.sin(&work)?);
+		Ok(Self::new(re, im).round(ctx))
+	}
+
+	// Principal logarithm: ln|z| + i arg z
+	pub fn ln(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Err("Logarithm is undefined at zero".to_string());
+		}
+		let work = working(ctx, 0.0);
+		let half = BigNumber::new(BigInt::from(5), 1);
+		let re = self.norm().ln(&work)?.multiply(&half);
+		let im = self.im.atan2(&self.re, &work)?;
+		Ok(Self::new(re, im).round(ctx))
+	}
+
+	// Integer powers by repeated squaring, anything else as exp(w ln z)
+	pub fn power(&self, exponent: &Self, ctx: &Context) -> Result<Self, String> {
+		if exponent.is_real() && exponent.re.is_integer() {
+			return self.integer_power(&exponent.re.to_integer(), ctx);
+		}
+		if self.is_zero() {
+			if exponent.re.is_negative() || exponent.re.is_zero() {
+				return Err("Zero cannot be raised to a power with a non-positive real part".to_string());
+			}
+			return Ok(self.clone());
+		}
+
+		// The result has about Re(w ln z) / ln 10 integer digits, which the product must cover
+		let rough = self.ln(&ctx.with_precision(10))?.multiply(exponent);
+		let magnitude = rough.re.to_f64() * std::f64::consts::LOG10_E;
+		check_result_digits(magnitude)?;
+		let work = working(ctx, magnitude);
+		let product = self.ln(&work)?.multiply(exponent).round(&work);
+		product.exp(ctx)
+	}
+
+	// sin(a + bi) = sin a cosh b + i cos a sinh b
+	pub fn sin(&self, ctx: &Context) -> Result<Self, String> {
+		let (sin, cos, cosh, sinh) = self.trig_parts(ctx)?;
+		Ok(Self::new(sin.multiply(&cosh), cos.multiply(&sinh)).round(ctx))
+	}
+
+	// cos(a + bi) = cos a cosh b - i sin a sinh b
+	pub fn cos(&self, ctx: &Context) -> Result<Self, String> {
+		let (sin, cos, cosh, sinh) = self.trig_parts(ctx)?;
+		Ok(Self::new(cos.multiply(&cosh), sin.multiply(&sinh).negate()).round(ctx))
+	}
+
+	pub fn tan(&self, ctx: &Context) -> Result<Self, String> {
+		let work = working(ctx, 0.0);
+		let (sin, cos, cosh, sinh) = self.trig_parts(&work)?;
+		let numerator = Self::new(sin.multiply(&cosh), cos.multiply(&sinh));
+		let denominator = Self::new(cos.multiply(&cosh), sin.multiply(&sinh).negate());
+		Ok(numerator.divide(&denominator, &work)?.round(ctx))
+	}
+
+	pub fn to_display_string(&self, ctx: &Context) -> String {
+		if ctx.polar {
+			// Modulus and angle in the context's unit, written so it can be typed back in
+			// The parts only carry the context precision, and a degree angle derived from them
+			// magnifies their rounding error, so the angle is shown with two digits less
+			let angle_ctx = ctx.with_precision((ctx.precision - 2).max(0));
+			return match (self.abs(ctx), self.arg(&angle_ctx)) {
+				(Ok(modulus), Ok(angle)) => format!("{}∠{}", modulus.to_display_string(ctx), angle.to_display_string(ctx)),
+				_ => "0".to_string(),
+			};
+		}
+
+		let magnitude = self.im.abs();
+		let coefficient = if magnitude == BigNumber::new(BigInt::one(), 0) {
+			String::new()
+		} else {
+			magnitude.to_display_string(ctx)
+		};
+		let sign = if self.im.is_negative() { "-" } else { "+" };
+		if self.re.is_zero() {
+			let sign = if self.im.is_negative() { "-" } else { "" };
+			return format!("{}{}i", sign, coefficient);
+		}
+		format!("{}{}{}i", self.re.to_display_string(ctx), sign, coefficient)
+	}
+
+	// sin a, cos a, cosh b and sinh b for z = a + bi, in radians whatever the context unit
+	fn trig_parts(&self, ctx: &Context) -> Result<(BigNumber, BigNumber, BigNumber, BigNumber), String> {
+		// cosh b and sinh b grow like e^|b| and scale the other two
+		let work = working(ctx, self.im.abs().to_f64() * std::f64::consts::LOG10_E);
+		Ok((self.re.sin(&work)?, self.re.cos(&work)?, self.im.cosh(&work)?, self.im.sinh(&work)?))
+	}
+
+	fn integer_power(&self, exponent: &BigInt, ctx: &Context) -> Result<Self, String> {
+		if exponent.is_zero() {
+			return Ok(Self::new(BigNumber::new(BigInt::one(), 0), BigNumber::new(BigInt::zero(), 0)));
+		}
+		if self.is_zero() {
+			if exponent < &BigInt::zero() {
+				return Err("Zero cannot be raised to a negative power".to_string());
+			}
+			return Ok(self.clone());
+		}
+
+		// Like BigNumber powers, both parts grow to about n times their current digits
+		let exp_f64 = exponent.to_f64().unwrap_or(f64::INFINITY).abs();
+		let digits = |part: &BigNumber| part.mantissa.to_string().len() as f64 + part.scale.abs() as f64;
+		check_result_digits(exp_f64 * digits(&self.re).max(digits(&self.im)))?;
+		let mut remaining = exponent.magnitude().to_u64().ok_or("Exponent too large")?;
+
+		let mut result = Self::new(BigNumber::new(BigInt::one(), 0), BigNumber::new(BigInt::zero(), 0));
+		let mut base = self.clone();
+		while remaining > 0 {
+			if remaining & 1 == 1 {
+				result = result.multiply(&base);
+			}
+			remaining >>= 1;
+			if remaining > 0 {
+				base = base.multiply(&base);
+			}
+		}
+
+		if exponent < &BigInt::zero() {
+			let one = Self::new(BigNumber::new(BigInt::one(), 0), BigNumber::new(BigInt::zero(), 0));
+			return one.divide(&result, ctx);
+		}
+		Ok(result.round(ctx))
+	}
+
+	// |z|^2, exact
+	fn norm(&self) -> BigNumber {
+		self.re.multiply(&self.re).add(&self.im.multiply(&self.im))
+	}
+
+	fn round(&self, ctx: &Context) -> Self {
+		Self::new(
+			self.re.scale_to(ctx.precision, ctx.rounding).normalize(),
+			self.im.scale_to(ctx.precision, ctx.rounding).normalize(),
+		)
+	}
+}
+
+// Guard digits for intermediate steps, plus `extra` for values that get scaled up by
+// that many digits afterwards. Complex functions always work in radians.
+fn working(ctx: &Context, extra: f64) -> Context {
+	let extra = if extra.is_finite() { extra.max(0.0).ceil() as i32 } else { 0 };
+	Context {
+		precision: ctx.precision + 10 + extra,
+		rounding: RoundingMode::HalfEven,
+		angle_unit: AngleUnit::Radians,
+		..ctx.clone()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn polar_display_at_low_precision() {
+		let z = Complex::new(BigNumber::new(BigInt::from(1), 0), BigNumber::new(BigInt::from(1), 0));
+		for (precision, expected) in [(0, "1∠1"), (1, "1.4∠1"), (2, "1.41∠1")] {
+			let ctx = Context { precision, polar: true, ..Context::default() };
+			assert_eq!(z.to_display_string(&ctx), expected);
+		}
+	}
+}
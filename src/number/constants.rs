@@ -0,0 +1,149 @@
+use num_bigint::BigInt;
+use num_traits::{One, Zero};
+use std::sync::Mutex;
+
+use super::{BigNumber, decimal_to_bits, integer_root};
+use crate::context::Context;
+
+// Most precise value computed so far for a constant, as (bits, value * 2^bits).
+// Any request for fewer bits is served by shifting it down.
+type Cache = Mutex<Option<(u64, BigInt)>>;
+
+static PI: Cache = Mutex::new(None);
+static E: Cache = Mutex::new(None);
+static PHI: Cache = Mutex::new(None);
+static EULER_GAMMA: Cache = Mutex::new(None);
+
+// Mathematical constants rounded to the context precision
+impl BigNumber {
+	pub fn pi(ctx: &Context) -> Self {
+		let bits = constant_bits(ctx);
+		Self::from_fixed(&pi_fixed(bits), bits, ctx)
+	}
+
+	pub fn e(ctx: &Context) -> Self {
+		let bits = constant_bits(ctx);
+		Self::from_fixed(&cached(&E, bits, e_fixed), bits, ctx)
+	}
+
+	// The golden ratio (1 + sqrt 5) / 2
+	pub fn phi(ctx: &Context) -> Self {
+		let bits = constant_bits(ctx);
+		Self::from_fixed(&cached(&PHI, bits, phi_fixed), bits, ctx)
+	}
+
+	// The Euler-Mascheroni constant, the limit of H_n - ln n
+	pub fn euler_gamma(ctx: &Context) -> Self {
+		let bits = constant_bits(ctx);
+		Self::from_fixed(&cached(&EULER_GAMMA, bits, euler_gamma_fixed), bits, ctx)
+	}
+}
+
+fn constant_bits(ctx: &Context) -> u64 {
+	decimal_to_bits(ctx.precision as f64) + 16
+}
+
+fn cached(cache: &Cache, bits: u64, compute: fn(u64) -> BigInt) -> BigInt {
+	let mut slot = cache.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+	if let Some((cached_bits, value)) = slot.as_ref()
+		&& *cached_bits >= bits {
+		return value >> (cached_bits - bits);
+	}
+	let value = compute(bits);
+	*slot = Some((bits, value.clone()));
+	value
+}
+
+pub(super) fn pi_fixed(bits: u64) -> BigInt {
+	cached(&PI, bits, chudnovsky_pi_fixed)
+}
+
+// Chudnovsky: 1/pi = 12 sum (-1)^k (6k)! (13591409 + 545140134 k) / ((3k)! (k!)^3 640320^(3k + 3/2)),
+// summed by binary splitting; every term adds about 47 bits
+fn chudnovsky_pi_fixed(bits: u64) -> BigInt {
+	let work = bits + 16;
+	let terms = work / 47 + 2;
+	let (_, q, t) = chudnovsky_split(0, terms);
+	let sqrt_10005 = integer_root(&(BigInt::from(10005) << (2 * work)), 2);
+	(q * 426880u32 * sqrt_10005 / t) >> 16
+}
+
+// (P, Q, T) for the terms a..b of the Chudnovsky series
+fn chudnovsky_split(a: u64, b: u64) -> (BigInt, BigInt, BigInt) {
+	if b - a == 1 {
+		let (p, q) = if a == 0 {
+			(BigInt::one(), BigInt::one())
+		} else {
+			let a_big = BigInt::from(a);
+			let p = BigInt::from(6 * a - 5) * (2 * a - 1) * (6 * a - 1);
+			// 640320^3 / 24
+			let q = a_big.pow(3) * 10_939_058_860_032_000u64;
+			(p, q)
+		};
+		let t = &p * (BigInt::from(545_140_134u64) * a + 13_591_409u64);
+		let t = if a % 2 == 1 { -t } else { t };
+		return (p, q, t);
+	}
+
+	let middle = (a + b) / 2;
+	let (p1, q1, t1) = chudnovsky_split(a, middle);
+	let (p2, q2, t2) = chudnovsky_split(middle, b);
+	let t = t1 * &q2 + &p1 * t2;
+	(p1 * p2, q1 * q2, t)
+}
+
+// e = sum 1/k!, with the partial sum P/Q built by binary splitting
+fn e_fixed(bits: u64) -> BigInt {
+	// Enough terms that the first one left out, 1/n!, is below 2^-(bits + 8)
+	let mut terms = 1u64;
+	let mut factorial_log2 = 0.0;
+	while factorial_log2 < (bits + 8) as f64 {
+		terms += 1;
+		factorial_log2 += (terms as f64).log2();
+	}
+
+	let (p, q) = factorial_split(0, terms);
+	(BigInt::one() << bits) + (p << bits) / q
+}
+
+// P/Q = sum over k in a+1..=b of 1 / ((a+1)(a+2)...k)
+fn factorial_split(a: u64, b: u64) -> (BigInt, BigInt) {
+	if b - a == 1 {
+		return (BigInt::one(), BigInt::from(b));
+	}
+	let middle = (a + b) / 2;
+	let (p1, q1) = factorial_split(a, middle);
+	let (p2, q2) = factorial_split(middle, b);
+	(p1 * &q2 + p2, q1 * q2)
+}
+
+fn phi_fixed(bits: u64) -> BigInt {
+	let sqrt_5 = integer_root(&(BigInt::from(5) << (2 * bits)), 2);
+	((BigInt::one() << bits) + sqrt_5) >> 1
+}
+
+// Brent-McMillan: with A_k = (n^k/k!)^2 (H_k - ln n) and B_k = (n^k/k!)^2,
+// gamma = sum A_k / sum B_k up to an error of about e^(-4n)
+fn euler_gamma_fixed(bits: u64) -> BigInt {
+	// A power of two for n makes ln n a multiple of ln 2 and n^2 a shift
+	let n_log2 = ((bits as f64 * std::f64::consts::LN_2 / 4.0).log2().ceil() as u64).max(1);
+	let work = bits + 32;
+
+	let mut b = BigInt::one() << work;
+	let mut a = -(super::ln2_fixed(work) * n_log2);
+	let mut a_sum = a.clone();
+	let mut b_sum = b.clone();
+	let mut k = 1u64;
+	loop {
+		b = (b << (2 * n_log2)) / (k * k);
+		a = ((a << (2 * n_log2)) / k + &b) / k;
+		if a.is_zero() && b.is_zero() {
+			break;
+		}
+		a_sum += &a;
+		b_sum += &b;
+		k += 1;
+	}
+
+	(a_sum << bits) / b_sum
+}
@@ -0,0 +1,121 @@
+use num_bigint::BigInt;
+use num_traits::One;
+
+use super::{BigNumber, RoundingMode};
+use crate::context::Context;
+
+// Hyperbolic functions and their inverses, built on exp and ln at a few guard digits
+impl BigNumber {
+	pub fn sinh(&self, ctx: &Context) -> Result<Self, String> {
+		// sinh is odd, so work with |x| and restore the sign before the final rounding
+		let (growing, decaying) = self.abs().exp_pair(ctx)?;
+		let magnitude = growing.subtract(&decaying).multiply(&half());
+		let value = if self.is_negative() { magnitude.negate() } else { magnitude };
+		Ok(value.scale_to(ctx.precision, ctx.rounding).normalize())
+	}
+
+	pub fn cosh(&self, ctx: &Context) -> Result<Self, String> {
+		let (growing, decaying) = self.abs().exp_pair(ctx)?;
+		let value = growing.add(&decaying).multiply(&half());
+		Ok(value.scale_to(ctx.precision, ctx.rounding).normalize())
+	}
+
+	pub fn tanh(&self, ctx: &Context) -> Result<Self, String> {
+		let negative = self.is_negative();
+		let magnitude = self.abs();
+
+		// 1 - |tanh x| is about 2 e^(-2|x|); once that is below the last place only
+		// rounding toward zero keeps the result under 1
+		if magnitude.to_f64() * 2.0 * std::f64::consts::LOG10_E > (ctx.precision + 2) as f64 {
+			let toward_zero = match ctx.rounding {
+				RoundingMode::Down => true,
+				RoundingMode::Floor => !negative,
+				RoundingMode::Ceiling => negative,
+				_ => false,
+			};
+			let one = Self::new(BigInt::one(), 0);
+			let value = if toward_zero { one.subtract(&Self::new(BigInt::one(), ctx.precision)) } else { one };
+			return Ok(if negative { value.negate() } else { value });
+		}
+
+		// tanh|x| = (1 - e^(-2|x|)) / (1 + e^(-2|x|)), where e^(-2|x|) lies in (0, 1]
+		let guarded = guarded(ctx);
+		let one = Self::new(BigInt::one(), 0);
+		let decaying = magnitude.multiply(&Self::new(BigInt::from(-2), 0)).exp(&guarded)?;
+		let value = one.subtract(&decaying).divide(&one.add(&decaying), &guarded)?;
+		let value = if negative { value.negate() } else { value };
+		Ok(value.scale_to(ctx.precision, ctx.rounding).normalize())
+	}
+
+	pub fn asinh(&self, ctx: &Context) -> Result<Self, String> {
+		// asinh|x| = ln(|x| + sqrt(x^2 + 1)), and asinh is odd
+		let magnitude = self.abs();
+		let value = match Self::large_argument_ln(&magnitude, ctx)? {
+			Some(log) => log,
+			None => {
+				let guarded = guarded(ctx);
+				let root = magnitude.multiply(&magnitude).add(&Self::new(BigInt::one(), 0)).sqrt(&guarded)?;
+				magnitude.add(&root).ln(&guarded)?
+			}
+		};
+		let value = if self.is_negative() { value.negate() } else { value };
+		Ok(value.scale_to(ctx.precision, ctx.rounding).normalize())
+	}
+
+	pub fn acosh(&self, ctx: &Context) -> Result<Self, String> {
+		let one = Self::new(BigInt::one(), 0);
+		if self.subtract(&one).is_negative() {
+			return Err("acosh is only defined for arguments of at least 1".to_string());
+		}
+
+		// acosh(x) = ln(x + sqrt(x^2 - 1))
+		let value = match Self::large_argument_ln(self, ctx)? {
+			Some(log) => log,
+			None => {
+				let guarded = guarded(ctx);
+				let root = self.multiply(self).subtract(&one).sqrt(&guarded)?;
+				self.add(&root).ln(&guarded)?
+			}
+		};
+		Ok(value.scale_to(ctx.precision, ctx.rounding).normalize())
+	}
+
+	pub fn atanh(&self, ctx: &Context) -> Result<Self, String> {
+		let one = Self::new(BigInt::one(), 0);
+		if !self.abs().subtract(&one).is_negative() {
+			return Err("atanh is only defined for arguments between -1 and 1 exclusive".to_string());
+		}
+
+		// atanh(x) = (ln(1 + x) - ln(1 - x)) / 2; both arguments are exact, so even
+		// x close to 1 keeps full precision
+		let guarded = guarded(ctx);
+		let difference = one.add(self).ln(&guarded)?.subtract(&one.subtract(self).ln(&guarded)?);
+		Ok(difference.multiply(&half()).scale_to(ctx.precision, ctx.rounding).normalize())
+	}
+
+	// e^x and e^-x for x >= 0
+	fn exp_pair(&self, ctx: &Context) -> Result<(Self, Self), String> {
+		let guarded = guarded(ctx);
+		let growing = self.exp(&guarded)?;
+		let decaying = Self::new(BigInt::one(), 0).divide(&growing, &guarded)?;
+		Ok((growing, decaying))
+	}
+
+	// ln(2x) for x so large that asinh(x) and acosh(x) agree with it beyond the guard
+	// digits; this also avoids squaring a huge argument
+	fn large_argument_ln(x: &Self, ctx: &Context) -> Result<Option<Self>, String> {
+		let guarded = guarded(ctx);
+		if x.log10_estimate() * 2.0 <= (guarded.precision + 1) as f64 {
+			return Ok(None);
+		}
+		x.multiply(&Self::new(BigInt::from(2), 0)).ln(&guarded).map(Some)
+	}
+}
+
+fn guarded(ctx: &Context) -> Context {
+	ctx.with_precision(ctx.precision + 5).with_rounding(RoundingMode::HalfEven)
+}
+
+fn half() -> BigNumber {
+	BigNumber::new(BigInt::from(5), 1)
+}
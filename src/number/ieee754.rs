@@ -0,0 +1,257 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+use std::fmt;
+
+use super::{BigNumber, Rational, RoundingMode, divide_rounded};
+
+// The binary interchange formats of IEEE 754, which Rust calls f16, f32, f64 and f128
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FloatFormat {
+	Binary16,
+	Binary32,
+	#[default]
+	Binary64,
+	Binary128,
+}
+
+impl FloatFormat {
+	pub const ALL: [FloatFormat; 4] = [
+		FloatFormat::Binary16,
+		FloatFormat::Binary32,
+		FloatFormat::Binary64,
+		FloatFormat::Binary128,
+	];
+
+	pub fn exponent_bits(self) -> u32 {
+		match self {
+			FloatFormat::Binary16 => 5,
+			FloatFormat::Binary32 => 8,
+			FloatFormat::Binary64 => 11,
+			FloatFormat::Binary128 => 15,
+		}
+	}
+
+	// Stored bits of the significand, one fewer than its precision because the leading
+	// bit of normal numbers is implicit
+	pub fn fraction_bits(self) -> u32 {
+		match self {
+			FloatFormat::Binary16 => 10,
+			FloatFormat::Binary32 => 23,
+			FloatFormat::Binary64 => 52,
+			FloatFormat::Binary128 => 112,
+		}
+	}
+
+	fn bias(self) -> i64 {
+		(1 << (self.exponent_bits() - 1)) - 1
+	}
+
+	// Exponent field of infinities and NaNs
+	fn max_field(self) -> u32 {
+		(1 << self.exponent_bits()) - 1
+	}
+}
+
+impl fmt::Display for FloatFormat {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		let name = match self {
+			FloatFormat::Binary16 => "binary16",
+			FloatFormat::Binary32 => "binary32",
+			FloatFormat::Binary64 => "binary64",
+			FloatFormat::Binary128 => "binary128",
+		};
+		write!(f, "{}", name)
+	}
+}
+
+// A finite or infinite value of one of the formats, as its three bit fields. NaNs never
+// come out of rounding a number, so they have no representation here.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Float {
+	pub format: FloatFormat,
+	pub negative: bool,
+	pub exponent: u32, // Biased exponent field, zero for subnormals
+	pub fraction: BigInt, // Significand without its implicit leading bit
+}
+
+impl Float {
+	// The value nearest to x, ties to even, the way Rust converts literals and the hardware
+	// rounds results. Too large for the format is infinity; too small is a signed zero.
+	pub fn nearest(x: &Rational, format: FloatFormat) -> Self {
+		let negative = x.is_negative();
+		let n = x.numerator().abs();
+		let d = x.denominator();
+		if n.is_zero() {
+			return Self { format, negative, exponent: 0, fraction: BigInt::zero() };
+		}
+		let infinity = Self { format, negative, exponent: format.max_field(), fraction: BigInt::zero() };
+
+		// e = floor(log2(n / d)), starting from the bit lengths which are at most one too high
+		let precision = format.fraction_bits() as i64 + 1;
+		let max_exponent = format.bias();
+		let min_exponent = 1 - format.bias();
+		let mut e = n.bits() as i64 - d.bits() as i64;
+		if e > max_exponent + 1 {
+			return infinity;
+		}
+		if scaled(&n, d, -e) < BigInt::one() {
+			e -= 1;
+		}
+		// Below the normal range the spacing stays that of the smallest exponent
+		let e = e.max(min_exponent);
+
+		let (numerator, denominator) = scale_by_power_of_two(&n, d, precision - 1 - e);
+		let mut significand = divide_rounded(&numerator, &denominator, RoundingMode::HalfEven);
+		let mut e = e;
+		// Rounding up to the next power of two moves into the next binade
+		if significand.bits() as i64 > precision {
+			significand >>= 1;
+			e += 1;
+		}
+		if e > max_exponent {
+			return infinity;
+		}
+		if (significand.bits() as i64) < precision {
+			return Self { format, negative, exponent: 0, fraction: significand };
+		}
+		let fraction = significand - (BigInt::one() << (precision - 1));
+		Self { format, negative, exponent: (e + format.bias()) as u32, fraction }
+	}
+
+	pub fn is_infinite(&self) -> bool {
+		self.exponent == self.format.max_field()
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.exponent == 0 && self.fraction.is_zero()
+	}
+
+	pub fn is_subnormal(&self) -> bool {
+		self.exponent == 0 && !self.fraction.is_zero()
+	}
+
+	// Power of two of the leading significand bit, which for subnormals is that of the
+	// smallest normal number
+	pub fn unbiased_exponent(&self) -> i64 {
+		self.exponent.max(1) as i64 - self.format.bias()
+	}
+
+	// The whole encoding: sign, exponent and fraction fields from the top bit down
+	pub fn to_bits(&self) -> BigInt {
+		let sign = if self.negative { BigInt::one() } else { BigInt::zero() };
+		let sign_and_exponent = (sign << self.format.exponent_bits()) + self.exponent;
+		(sign_and_exponent << self.format.fraction_bits()) + &self.fraction
+	}
+
+	// Exact value as a fraction, or None for infinity
+	pub fn to_rational(&self) -> Option<Rational> {
+		let (numerator, denominator) = self.dyadic()?;
+		Rational::new(numerator, denominator).ok()
+	}
+
+	// Exact value in decimal, which always terminates since it has a power of two below it:
+	// m / 2^k = m * 5^k / 10^k. None for infinity.
+	pub fn to_exact_decimal(&self) -> Option<BigNumber> {
+		let (numerator, denominator) = self.dyadic()?;
+		let k = denominator.bits() - 1;
+		let mantissa = numerator * BigInt::from(5).pow(k as u32);
+		Some(BigNumber::new(mantissa, k as i32).normalize())
+	}
+
+	// How far the value is from x, positive when it is above: float - x. None for infinity.
+	pub fn rounding_error(&self, x: &Rational) -> Option<Rational> {
+		Some(self.to_rational()?.subtract(x))
+	}
+
+	// The rounding error in units in the last place, the spacing of values around this one.
+	// Rounding to nearest keeps it within half a unit.
+	pub fn error_in_ulps(&self, x: &Rational) -> Option<Rational> {
+		let error = self.rounding_error(x)?;
+		let ulp_exponent = self.unbiased_exponent() - self.format.fraction_bits() as i64;
+		let (numerator, denominator) = scale_by_power_of_two(error.numerator(), error.denominator(), -ulp_exponent);
+		Rational::new(numerator, denominator).ok()
+	}
+
+	// Value as numerator and power-of-two denominator, not reduced
+	fn dyadic(&self) -> Option<(BigInt, BigInt)> {
+		if self.is_infinite() {
+			return None;
+		}
+		let implicit = if self.exponent == 0 { BigInt::zero() } else { BigInt::one() << self.format.fraction_bits() };
+		let significand = implicit + &self.fraction;
+		let significand = if self.negative { -significand } else { significand };
+		let (numerator, denominator) = scale_by_power_of_two(
+			&significand,
+			&BigInt::one(),
+			self.unbiased_exponent() - self.format.fraction_bits() as i64,
+		);
+		Some((numerator, denominator))
+	}
+}
+
+impl BigNumber {
+	pub fn to_float(&self, format: FloatFormat) -> Float {
+		Float::nearest(&self.to_rational(), format)
+	}
+}
+
+// n / d * 2^shift as a numerator and denominator
+fn scale_by_power_of_two(n: &BigInt, d: &BigInt, shift: i64) -> (BigInt, BigInt) {
+	if shift >= 0 {
+		(n << shift as u64, d.clone())
+	} else {
+		(n.clone(), d << (-shift) as u64)
+	}
+}
+
+// n / d * 2^shift truncated to an integer
+fn scaled(n: &BigInt, d: &BigInt, shift: i64) -> BigInt {
+	let (numerator, denominator) = scale_by_power_of_two(n, d, shift);
+	numerator / denominator
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::number::parse_number;
+	use num_traits::ToPrimitive;
+
+	fn nearest(literal: &str, format: FloatFormat) -> Float {
+		Float::nearest(&parse_number(literal).unwrap().to_rational(), format)
+	}
+
+	#[test]
+	fn agrees_with_rust_conversions() {
+		let literals = [
+			"0.1", "0.3", "-7.25", "9007199254740993", "1e23", "1.7976931348623157e308", "1.7976931348623159e308",
+			"2.2250738585072011e-308", "2.4703282292062328e-324", "2.4703282292062327e-324", "3.4028235e38",
+			"1.17549435e-38", "7e-46", "16777217", "123456789.123456789",
+		];
+		for literal in literals {
+			let f64_bits = nearest(literal, FloatFormat::Binary64).to_bits().to_u64().unwrap();
+			assert_eq!(f64_bits, literal.parse::<f64>().unwrap().to_bits(), "f64 {}", literal);
+			let f32_bits = nearest(literal, FloatFormat::Binary32).to_bits().to_u32().unwrap();
+			assert_eq!(f32_bits, literal.parse::<f32>().unwrap().to_bits(), "f32 {}", literal);
+		}
+	}
+
+	#[test]
+	fn exact_value_and_error_of_a_tenth() {
+		let tenth = parse_number("0.1").unwrap().to_rational();
+		let float = Float::nearest(&tenth, FloatFormat::Binary64);
+		assert_eq!(float.to_exact_decimal().unwrap().to_standard_string(), "0.1000000000000000055511151231257827021181583404541015625");
+		let ulps = float.error_in_ulps(&tenth).unwrap();
+		assert_eq!(ulps, Rational::new(BigInt::from(2), BigInt::from(5)).unwrap());
+	}
+
+	#[test]
+	fn overflow_underflow_and_subnormals() {
+		assert!(nearest("65520", FloatFormat::Binary16).is_infinite());
+		assert_eq!(nearest("65519", FloatFormat::Binary16).to_exact_decimal(), Some(parse_number("65504").unwrap()));
+		let smallest = nearest("5e-324", FloatFormat::Binary64);
+		assert!(smallest.is_subnormal());
+		assert_eq!(smallest.to_bits(), BigInt::one());
+		let zero = nearest("-1e-5000", FloatFormat::Binary128);
+		assert!(zero.is_zero() && zero.negative);
+	}
+}
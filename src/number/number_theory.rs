@@ -0,0 +1,141 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, Zero};
+
+use super::BigNumber;
+use super::primes::factorize;
+use crate::context::Context;
+
+// Integer functions for modular arithmetic, on the mantissas of integral values
+impl BigNumber {
+	// Greatest common divisor of all arguments, never negative
+	pub fn gcd(values: &[Self]) -> Result<Self, String> {
+		let integers = Self::integer_arguments("gcd", values)?;
+		let value = integers.iter().fold(BigInt::zero(), |acc, n| gcd(&acc, n));
+		Ok(Self::new(value, 0))
+	}
+
+	// Least common multiple of all arguments, zero if any of them is
+	pub fn lcm(values: &[Self]) -> Result<Self, String> {
+		let integers = Self::integer_arguments("lcm", values)?;
+		let value = integers.iter().fold(BigInt::one(), |acc, n| {
+			if acc.is_zero() || n.is_zero() {
+				return BigInt::zero();
+			}
+			(&acc / gcd(&acc, n) * n).abs()
+		});
+		Ok(Self::new(value, 0))
+	}
+
+	// self^exponent mod modulus in [0, modulus); a negative exponent raises the inverse
+	pub fn modpow(&self, exponent: &Self, modulus: &Self) -> Result<Self, String> {
+		let base = self.integer_argument("modpow")?;
+		let exponent = exponent.integer_argument("modpow")?;
+		let modulus = modulus.modulus_argument("modpow")?;
+		let base = if exponent.is_negative() { mod_inverse(&base, &modulus)? } else { base };
+		let value = modulo(&base, &modulus).modpow(&exponent.abs(), &modulus);
+		Ok(Self::new(value, 0))
+	}
+
+	// The x in [0, modulus) with self * x = 1 mod modulus
+	pub fn modinv(&self, modulus: &Self) -> Result<Self, String> {
+		let value = self.integer_argument("modinv")?;
+		let modulus = modulus.modulus_argument("modinv")?;
+		Ok(Self::new(mod_inverse(&value, &modulus)?, 0))
+	}
+
+	// Euler's phi: how many of 1..=n are coprime to n, from the prime factorization
+	pub fn totient(&self, ctx: &Context) -> Result<Self, String> {
+		let n = self.integer_argument("totient")?;
+		if !n.is_positive() {
+			return Err(format!("totient() needs a positive integer, got {}", n));
+		}
+		let value = factorize(&n, ctx)?.iter().fold(BigInt::one(), |acc, (prime, exponent)| {
+			acc * prime.pow(exponent - 1) * (prime - 1)
+		});
+		Ok(Self::new(value, 0))
+	}
+
+	// Smallest x >= 0 with x = r1 mod m1, x = r2 mod m2, ..., from the arguments
+	// r1, m1, r2, m2, ... The moduli need not be coprime as long as the congruences agree.
+	pub fn crt(arguments: &[Self]) -> Result<Self, String> {
+		if arguments.is_empty() || !arguments.len().is_multiple_of(2) {
+			return Err("crt() takes pairs of remainder and modulus, like crt(2, 3, 3, 5)".to_string());
+		}
+		let mut value = BigInt::zero();
+		let mut combined = BigInt::one();
+		for pair in arguments.chunks(2) {
+			let remainder = pair[0].integer_argument("crt")?;
+			let modulus = pair[1].modulus_argument("crt")?;
+
+			// x = value + combined * t must also be remainder mod modulus
+			let divisor = gcd(&combined, &modulus);
+			let difference = &remainder - &value;
+			if !(&difference % &divisor).is_zero() {
+				return Err(format!("No number is {} mod {} and {} mod {}", value, combined, remainder, modulus));
+			}
+			let reduced = &modulus / &divisor;
+			let t = modulo(&(difference / &divisor * mod_inverse(&(&combined / &divisor), &reduced)?), &reduced);
+			value += &combined * t;
+			combined *= reduced;
+			value = modulo(&value, &combined);
+		}
+		Ok(Self::new(value, 0))
+	}
+
+	fn integer_arguments(name: &str, values: &[Self]) -> Result<Vec<BigInt>, String> {
+		if values.is_empty() {
+			return Err(format!("{}() needs at least one argument", name));
+		}
+		values.iter().map(|value| value.integer_argument(name)).collect()
+	}
+
+	pub(super) fn integer_argument(&self, name: &str) -> Result<BigInt, String> {
+		if !self.is_integer() {
+			return Err(format!("{}() needs integers, got {}", name, self.to_standard_string()));
+		}
+		Ok(self.to_integer())
+	}
+
+	fn modulus_argument(&self, name: &str) -> Result<BigInt, String> {
+		let modulus = self.integer_argument(name)?;
+		if !modulus.is_positive() {
+			return Err(format!("{}() needs a positive modulus, got {}", name, modulus));
+		}
+		Ok(modulus)
+	}
+}
+
+// Greatest common divisor by Euclid's algorithm, zero only when both are zero
+pub(super) fn gcd(a: &BigInt, b: &BigInt) -> BigInt {
+	let mut a = a.abs();
+	let mut b = b.abs();
+	while !b.is_zero() {
+		let remainder = &a % &b;
+		a = b;
+		b = remainder;
+	}
+	a
+}
+
+// a mod m in [0, m) for a positive m
+pub(super) fn modulo(a: &BigInt, m: &BigInt) -> BigInt {
+	let remainder = a % m;
+	if remainder.is_negative() { remainder + m } else { remainder }
+}
+
+// Inverse of a modulo m by the extended Euclidean algorithm
+pub(super) fn mod_inverse(a: &BigInt, m: &BigInt) -> Result<BigInt, String> {
+	let (mut old_r, mut r) = (modulo(a, m), m.clone());
+	let (mut old_s, mut s) = (BigInt::one(), BigInt::zero());
+	while !r.is_zero() {
+		let quotient = &old_r / &r;
+		let next_r = &old_r - &quotient * &r;
+		old_r = std::mem::replace(&mut r, next_r);
+		let next_s = &old_s - &quotient * &s;
+		old_s = std::mem::replace(&mut s, next_s);
+	}
+	if !old_r.is_one() {
+		return Err(format!("{} has no inverse mod {}, since they share the factor {}", a, m, old_r));
+	}
+	Ok(modulo(&old_s, m))
+}
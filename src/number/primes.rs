@@ -0,0 +1,537 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use super::number_theory::{gcd, mod_inverse, modulo};
+use super::{BigNumber, integer_root};
+use crate::context::Context;
+
+// Primes below this are found by trial division before the general methods take over
+const TRIAL_DIVISION_LIMIT: u64 = 10_000;
+// Pollard's rho usually finds factors up to about 10^9 within this many steps;
+// the elliptic curve method is faster beyond that
+const MAX_RHO_STEPS: u64 = 1 << 16;
+// Bound B1 for stage 1 of the elliptic curve method and how many curves to try with
+// it, aimed at factors of about 15, 20, 25, 30, 35 and 40 digits. The last entry
+// repeats until a factor turns up or the calculation is cancelled.
+const ECM_SCHEDULE: [(u64, u32); 6] = [(2_000, 25), (11_000, 90), (50_000, 300), (250_000, 700), (1_000_000, 1800), (3_000_000, 5100)];
+
+// Primality and factorization. The searches can run for a long time on large
+// inputs, so they stop with an error once the context's calculation is cancelled.
+impl BigNumber {
+	// 1 for primes and 0 for everything else
+	pub fn is_prime(&self) -> Result<Self, String> {
+		let prime = is_probable_prime(&self.integer_argument("isprime")?);
+		Ok(Self::new(BigInt::from(prime as u8), 0))
+	}
+
+	// Smallest prime above self
+	pub fn next_prime(&self, ctx: &Context) -> Result<Self, String> {
+		let n = self.integer_argument("nextprime")?;
+		let mut candidate = if n < BigInt::from(2) { BigInt::from(2) } else { n + 1 };
+		while !is_probable_prime(&candidate) {
+			ctx.cancellation.check()?;
+			candidate += 1;
+		}
+		Ok(Self::new(candidate, 0))
+	}
+
+	// Largest prime below self
+	pub fn prev_prime(&self, ctx: &Context) -> Result<Self, String> {
+		let n = self.integer_argument("prevprime")?;
+		if n <= BigInt::from(2) {
+			return Err(format!("There is no prime below {}", n));
+		}
+		let mut candidate = n - 1;
+		while !is_probable_prime(&candidate) {
+			ctx.cancellation.check()?;
+			candidate -= 1;
+		}
+		Ok(Self::new(candidate, 0))
+	}
+
+	// Prime factors with their exponents in increasing order, led by (-1, 1) for a
+	// negative number. The factorization of 1 is empty.
+	pub fn factor(&self, ctx: &Context) -> Result<Vec<(BigInt, u32)>, String> {
+		let n = self.integer_argument("factor")?;
+		if n.is_zero() {
+			return Err("0 has no prime factorization".to_string());
+		}
+		let mut factors = factorize(&n, ctx)?;
+		if n.is_negative() {
+			factors.insert(0, (BigInt::from(-1), 1));
+		}
+		Ok(factors)
+	}
+}
+
+// Sieve of Eratosthenes
+pub(super) fn primes_up_to(n: u64) -> Vec<u64> {
+	let n = n as usize;
+	let mut composite = vec![false; n + 1];
+	let mut primes = Vec::new();
+	for i in 2..=n {
+		if composite[i] {
+			continue;
+		}
+		primes.push(i as u64);
+		for multiple in (i * i..=n).step_by(i) {
+			composite[multiple] = true;
+		}
+	}
+	primes
+}
+
+// Baillie-PSW test: a strong probable prime test to base 2 followed by a strong Lucas
+// test. It is exact below 2^64, and no composite passing it is known above that.
+pub(super) fn is_probable_prime(n: &BigInt) -> bool {
+	if n < &BigInt::from(2) {
+		return false;
+	}
+	for p in [2u32, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37] {
+		if n == &BigInt::from(p) {
+			return true;
+		}
+		if (n % p).is_zero() {
+			return false;
+		}
+	}
+	is_strong_probable_prime(n, &BigInt::from(2)) && is_strong_lucas_probable_prime(n)
+}
+
+// Miller-Rabin round for an odd n > 2
+fn is_strong_probable_prime(n: &BigInt, base: &BigInt) -> bool {
+	// n - 1 = d 2^s with d odd
+	let n_minus_one: BigInt = n - 1;
+	let s = n_minus_one.trailing_zeros().unwrap_or(0);
+	let d = &n_minus_one >> s;
+	let mut x = base.modpow(&d, n);
+	if x.is_one() || x == n_minus_one {
+		return true;
+	}
+	for _ in 1..s {
+		x = &x * &x % n;
+		if x == n_minus_one {
+			return true;
+		}
+	}
+	false
+}
+
+// Strong Lucas test for an odd n with Selfridge's parameters: the first D in
+// 5, -7, 9, -11, ... with Jacobi symbol (D/n) = -1, P = 1 and Q = (1 - D) / 4
+fn is_strong_lucas_probable_prime(n: &BigInt) -> bool {
+	// Squares have no such D, and the search would never end
+	if integer_root(n, 2).pow(2) == *n {
+		return false;
+	}
+	let mut d = BigInt::from(5);
+	loop {
+		match jacobi(&d, n) {
+			-1 => break,
+			0 if d.abs() != *n => return false,
+			_ => {}
+		}
+		d = if d.is_positive() { -(d + 2u32) } else { -(d - 2u32) };
+	}
+	let q = (BigInt::one() - &d) / 4;
+
+	// n + 1 = k 2^s with k odd; n passes if U_k = 0 or V_(k 2^r) = 0 for some r < s
+	let n_plus_one: BigInt = n + 1;
+	let s = n_plus_one.trailing_zeros().unwrap_or(0);
+	let k = &n_plus_one >> s;
+	let (u, mut v, mut q_power) = lucas_sequence(n, &d, &q, &k);
+	if u.is_zero() || v.is_zero() {
+		return true;
+	}
+	for _ in 1..s {
+		// V_2m = V_m^2 - 2 Q^m
+		v = modulo(&(&v * &v - &q_power * 2), n);
+		if v.is_zero() {
+			return true;
+		}
+		q_power = &q_power * &q_power % n;
+	}
+	false
+}
+
+// U_k, V_k and Q^k mod n for the Lucas sequences with P = 1, by binary doubling
+fn lucas_sequence(n: &BigInt, d: &BigInt, q: &BigInt, k: &BigInt) -> (BigInt, BigInt, BigInt) {
+	// Halving mod an odd n: add n first when the value is odd
+	let half = |x: BigInt| {
+		let x = modulo(&x, n);
+		if x.bit(0) { (x + n) >> 1 } else { x >> 1 }
+	};
+	let q = modulo(q, n);
+	let (mut u, mut v, mut q_power) = (BigInt::one(), BigInt::one(), q.clone());
+	for i in (0..k.bits() - 1).rev() {
+		// U_2m = U_m V_m, V_2m = V_m^2 - 2 Q^m
+		u = &u * &v % n;
+		v = modulo(&(&v * &v - &q_power * 2), n);
+		q_power = &q_power * &q_power % n;
+		if k.bit(i) {
+			// U_(m+1) = (U_m + V_m) / 2, V_(m+1) = (D U_m + V_m) / 2
+			let next_u = half(&u + &v);
+			v = half(d * &u + &v);
+			u = next_u;
+			q_power = &q_power * &q % n;
+		}
+	}
+	(u, v, q_power)
+}
+
+// Jacobi symbol (a/n) for an odd n > 0
+fn jacobi(a: &BigInt, n: &BigInt) -> i32 {
+	let mut a = modulo(a, n);
+	let mut n = n.clone();
+	let mut result = 1;
+	while !a.is_zero() {
+		let twos = a.trailing_zeros().unwrap_or(0);
+		a >>= twos;
+		// (2/n) = -1 exactly when n = 3 or 5 mod 8
+		let n_mod_8 = (&n % 8u32).to_u32().unwrap_or(0);
+		if twos % 2 == 1 && (n_mod_8 == 3 || n_mod_8 == 5) {
+			result = -result;
+		}
+		// Quadratic reciprocity flips the sign when both are 3 mod 4
+		if (&a % 4u32).to_u32() == Some(3) && n_mod_8 % 4 == 3 {
+			result = -result;
+		}
+		std::mem::swap(&mut a, &mut n);
+		a %= &n;
+	}
+	if n.is_one() { result } else { 0 }
+}
+
+// Prime factorization of |n| >= 1 as (prime, exponent) pairs in increasing order
+pub(super) fn factorize(n: &BigInt, ctx: &Context) -> Result<Vec<(BigInt, u32)>, String> {
+	let mut factors: Vec<BigInt> = Vec::new();
+	let mut remaining = n.abs();
+
+	for p in primes_up_to(TRIAL_DIVISION_LIMIT) {
+		if remaining.is_one() {
+			break;
+		}
+		while (&remaining % p).is_zero() {
+			remaining /= p;
+			factors.push(BigInt::from(p));
+		}
+	}
+
+	// Whatever is left has no small factors; split it until every part is prime
+	let mut composites = Vec::new();
+	if !remaining.is_one() {
+		composites.push(remaining);
+	}
+	while let Some(m) = composites.pop() {
+		ctx.cancellation.check()?;
+		if is_probable_prime(&m) {
+			factors.push(m);
+			continue;
+		}
+		let divisor = match perfect_power_root(&m) {
+			Some(root) => root,
+			None => match pollard_brent(&m, ctx)? {
+				Some(divisor) => divisor,
+				None => ecm(&m, ctx)?,
+			},
+		};
+		composites.push(&m / &divisor);
+		composites.push(divisor);
+	}
+
+	factors.sort();
+	let mut grouped: Vec<(BigInt, u32)> = Vec::new();
+	for factor in factors {
+		match grouped.last_mut() {
+			Some((prime, exponent)) if *prime == factor => *exponent += 1,
+			_ => grouped.push((factor, 1)),
+		}
+	}
+	Ok(grouped)
+}
+
+// r when m = r^k for some k >= 2, which both rho and ECM are slow to split
+fn perfect_power_root(m: &BigInt) -> Option<BigInt> {
+	// m has no prime factors below the trial division limit, which bounds k
+	let max_exponent = (m.bits() as f64 / (TRIAL_DIVISION_LIMIT as f64).log2()) as u32;
+	(2..=max_exponent).find_map(|k| {
+		let root = integer_root(m, k);
+		if root.pow(k) == *m { Some(root) } else { None }
+	})
+}
+
+// A non-trivial divisor of the composite n by Brent's variant of Pollard's rho,
+// iterating x -> x^2 + c and batching the gcd computations. None once the step
+// budget runs out, when the factors are too large for this method.
+fn pollard_brent(n: &BigInt, ctx: &Context) -> Result<Option<BigInt>, String> {
+	const BATCH: u64 = 128;
+	let mut steps = 0;
+
+	for c in 1u32..=20 {
+		let step = |x: &BigInt| (x * x + c) % n;
+		let mut y = BigInt::from(2);
+		let mut x = y.clone();
+		let mut saved = y.clone();
+		let mut product = BigInt::one();
+		let mut divisor = BigInt::one();
+		let mut length = 1;
+
+		while divisor.is_one() {
+			x = y.clone();
+			for _ in 0..length {
+				y = step(&y);
+			}
+			let mut done = 0;
+			while done < length && divisor.is_one() {
+				saved = y.clone();
+				for _ in 0..BATCH.min(length - done) {
+					y = step(&y);
+					product = product * (&x - &y).abs() % n;
+				}
+				divisor = gcd(&product, n);
+				done += BATCH;
+			}
+			steps += length;
+			length *= 2;
+			if steps > MAX_RHO_STEPS {
+				return Ok(None);
+			}
+			ctx.cancellation.check()?;
+		}
+
+		// The batch overshot; step through it again one gcd at a time
+		if &divisor == n {
+			loop {
+				saved = step(&saved);
+				divisor = gcd(&(&x - &saved), n);
+				if !divisor.is_one() {
+					break;
+				}
+			}
+		}
+		if &divisor != n {
+			return Ok(Some(divisor));
+		}
+		// Otherwise the cycle closed without a factor, and another polynomial may do better
+	}
+	Ok(None)
+}
+
+// A non-trivial divisor of the composite n by Lenstra's elliptic curve method. Keeps
+// trying curves, with larger bounds as they fail, until one succeeds or the
+// calculation is cancelled.
+fn ecm(n: &BigInt, ctx: &Context) -> Result<BigInt, String> {
+	let mut sigma = 6;
+	let last = ECM_SCHEDULE[ECM_SCHEDULE.len() - 1];
+	for (b1, curves) in ECM_SCHEDULE.into_iter().chain(std::iter::repeat(last)) {
+		let primes = primes_up_to(b1);
+		for _ in 0..curves {
+			sigma += 1;
+			let divisor = match Curve::suyama(n, sigma) {
+				Ok(curve) => curve.find_factor(b1, &primes, ctx)?,
+				Err(divisor) => divisor,
+			};
+			if !divisor.is_one() && &divisor != n {
+				return Ok(divisor);
+			}
+		}
+	}
+	unreachable!("the last bound repeats forever")
+}
+
+// Point on a Montgomery curve b y^2 = x^3 + a x^2 + x in projective (X : Z) form,
+// which leaves out y. That is enough to add points whose difference is known.
+#[derive(Clone)]
+struct Point {
+	x: BigInt,
+	z: BigInt,
+}
+
+struct Curve<'a> {
+	n: &'a BigInt,
+	a24: BigInt, // (a + 2) / 4, the only form of a the formulas need
+	start: Point,
+}
+
+impl<'a> Curve<'a> {
+	// Suyama's curve for the parameter sigma, whose group order has 12 as a factor.
+	// Setting it up needs an inverse mod n; when that fails, the divisor it ran into
+	// comes back instead.
+	fn suyama(n: &'a BigInt, sigma: u64) -> Result<Self, BigInt> {
+		let sigma = BigInt::from(sigma);
+		let u = modulo(&(&sigma * &sigma - 5), n);
+		let v: BigInt = &sigma * 4 % n;
+		let x = u.modpow(&BigInt::from(3), n);
+		let z = v.modpow(&BigInt::from(3), n);
+		// (a + 2) / 4 = (v - u)^3 (3u + v) / (16 u^3 v)
+		let numerator = modulo(&((&v - &u).pow(3) * (&u * 3 + &v)), n);
+		let denominator = &x * &v * 16 % n;
+		let inverse = mod_inverse(&denominator, n).map_err(|_| gcd(&denominator, n))?;
+		Ok(Self { n, a24: numerator * inverse % n, start: Point { x, z } })
+	}
+
+	// Stage 1 multiplies the starting point by every prime power up to b1. If the group
+	// order mod some prime factor p of n is that smooth, the result is the identity
+	// mod p and p divides its Z. Stage 2 allows one more prime factor q of the order up
+	// to 100 b1, writing q = j D +- b and collecting X_jD Z_b - X_b Z_jD, which vanishes
+	// mod p when q P is the identity there.
+	fn find_factor(&self, b1: u64, primes: &[u64], ctx: &Context) -> Result<BigInt, String> {
+		const D: u64 = 210;
+		let n = self.n;
+
+		let mut point = self.start.clone();
+		for (i, &p) in primes.iter().enumerate() {
+			if i % 256 == 0 {
+				ctx.cancellation.check()?;
+			}
+			let mut power = p;
+			while power * p <= b1 {
+				power *= p;
+			}
+			point = self.multiply(&point, power);
+		}
+		let divisor = gcd(&point.z, n);
+		if !divisor.is_one() {
+			return Ok(divisor);
+		}
+
+		// Odd multiples b P for b up to D / 2, built with (b + 2) P = b P + 2 P
+		let double = self.double(&point);
+		let mut multiples = vec![point.clone(), self.add(&double, &point, &point)];
+		while multiples.len() <= (D / 4) as usize {
+			let next = self.add(&multiples[multiples.len() - 1], &double, &multiples[multiples.len() - 2]);
+			multiples.push(next);
+		}
+		// Only b coprime to D = 2 3 5 7 can be part of a prime q
+		let babies: Vec<&Point> = (1..D / 2)
+			.step_by(2)
+			.filter(|b| b % 3 != 0 && b % 5 != 0 && b % 7 != 0)
+			.map(|b| &multiples[(b / 2) as usize])
+			.collect();
+
+		let step = self.multiply(&point, D);
+		let first = b1 / D;
+		let mut previous = self.multiply(&point, (first - 1) * D);
+		let mut giant = self.multiply(&point, first * D);
+		let mut product = BigInt::one();
+		for j in first..=100 * b1 / D + 1 {
+			if j % 64 == 0 {
+				ctx.cancellation.check()?;
+			}
+			for baby in &babies {
+				product = product * modulo(&(&giant.x * &baby.z - &baby.x * &giant.z), n) % n;
+			}
+			let next = self.add(&giant, &step, &previous);
+			previous = std::mem::replace(&mut giant, next);
+		}
+		Ok(gcd(&product, n))
+	}
+
+	fn double(&self, p: &Point) -> Point {
+		let n = self.n;
+		let sum = (&p.x + &p.z).pow(2) % n;
+		let difference = modulo(&(&p.x - &p.z), n).pow(2) % n;
+		let cross = modulo(&(&sum - &difference), n);
+		Point {
+			x: &sum * &difference % n,
+			z: &cross * ((&difference + &self.a24 * &cross) % n) % n,
+		}
+	}
+
+	// p + q, given their difference p - q
+	fn add(&self, p: &Point, q: &Point, difference: &Point) -> Point {
+		let n = self.n;
+		let u = modulo(&(&p.x - &p.z), n) * (&q.x + &q.z) % n;
+		let v = (&p.x + &p.z) * modulo(&(&q.x - &q.z), n) % n;
+		let sum = (&u + &v) % n;
+		let cross = modulo(&(&u - &v), n);
+		Point {
+			x: &difference.z * (&sum * &sum % n) % n,
+			z: &difference.x * (&cross * &cross % n) % n,
+		}
+	}
+
+	// k p for k >= 1 by the Montgomery ladder, which keeps r1 - r0 = p throughout
+	fn multiply(&self, p: &Point, k: u64) -> Point {
+		let mut r0 = p.clone();
+		let mut r1 = self.double(p);
+		for i in (0..63 - k.leading_zeros()).rev() {
+			if (k >> i) & 1 == 1 {
+				r0 = self.add(&r1, &r0, p);
+				r1 = self.double(&r1);
+			} else {
+				r1 = self.add(&r1, &r0, p);
+				r0 = self.double(&r0);
+			}
+		}
+		r0
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn factors_of(n: &BigInt) -> Vec<(BigInt, u32)> {
+		factorize(n, &Context::default()).unwrap()
+	}
+
+	#[test]
+	fn primality_agrees_with_trial_division() {
+		let naive = |n: u64| n >= 2 && (2..).take_while(|d| d * d <= n).all(|d| !n.is_multiple_of(d));
+		for n in 0..5000u64 {
+			assert_eq!(is_probable_prime(&BigInt::from(n)), naive(n), "{}", n);
+		}
+	}
+
+	#[test]
+	fn pseudoprimes_are_composite() {
+		// Carmichael numbers and strong pseudoprimes to base 2, the last one to every base up to 37
+		for n in [561u64, 1105, 1729, 41041, 2047, 3277, 4033, 4681, 8321, 3215031751, 3825123056546413051] {
+			assert!(!is_probable_prime(&BigInt::from(n)), "{}", n);
+		}
+	}
+
+	#[test]
+	fn mersenne_numbers() {
+		let mersenne = |p: u32| (BigInt::one() << p) - 1;
+		assert!(is_probable_prime(&mersenne(61)));
+		assert!(is_probable_prime(&mersenne(127)));
+		assert!(!is_probable_prime(&mersenne(67)));
+	}
+
+	#[test]
+	fn factors_semiprimes() {
+		// Cole's factorization of 2^67 - 1, within reach of Pollard's rho
+		let mersenne = (BigInt::one() << 67) - 1;
+		let expected = vec![(BigInt::from(193707721u64), 1), (BigInt::from(761838257287u64), 1)];
+		assert_eq!(factors_of(&mersenne), expected);
+
+		// Thirteen-digit factors are left to the elliptic curve method
+		let ctx = Context::default();
+		let p = BigNumber::new(BigInt::from(10u64.pow(12)), 0).next_prime(&ctx).unwrap().to_integer();
+		let q = BigNumber::new(BigInt::from(3 * 10u64.pow(12)), 0).next_prime(&ctx).unwrap().to_integer();
+		assert_eq!(factors_of(&(&p * &q)), vec![(p, 1), (q, 1)]);
+	}
+
+	#[test]
+	fn factors_powers_and_signs() {
+		assert_eq!(factors_of(&BigInt::from(360)), vec![(BigInt::from(2), 3), (BigInt::from(3), 2), (BigInt::from(5), 1)]);
+		let power = BigInt::from(1_000_003u64).pow(3);
+		assert_eq!(factors_of(&power), vec![(BigInt::from(1_000_003u64), 3)]);
+
+		let ctx = Context::default();
+		let negative = BigNumber::new(BigInt::from(-12), 0).factor(&ctx).unwrap();
+		assert_eq!(negative, vec![(BigInt::from(-1), 1), (BigInt::from(2), 2), (BigInt::from(3), 1)]);
+		assert!(BigNumber::new(BigInt::zero(), 0).factor(&ctx).is_err());
+		assert!(BigNumber::new(BigInt::from(2), 0).prev_prime(&ctx).is_err());
+	}
+
+	#[test]
+	fn cancelled_factorization_stops() {
+		let ctx = Context::default();
+		ctx.cancellation.cancel();
+		let semiprime = BigInt::from(1_000_000_007u64) * BigInt::from(998_244_353u64);
+		assert_eq!(factorize(&semiprime, &ctx), Err("Cancelled".to_string()));
+	}
+}
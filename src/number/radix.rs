@@ -0,0 +1,95 @@
+use num_bigint::BigInt;
+use num_traits::{Signed, Zero};
+use std::f64::consts::LN_10;
+
+use super::{BigNumber, Rational, divide_rounded, repeating_expansion};
+use crate::context::Context;
+
+// Positional notation in bases 2 to 36, where the letters A to Z are the digits after 9
+impl BigNumber {
+	// Exact value of the digits of a literal in the given base, like "FF", "0.1" or the
+	// repeating "0.(0011)"
+	pub(super) fn from_radix(s: &str, radix: u32) -> Result<Rational, String> {
+		let (fixed, repetend) = match s.strip_suffix(')').and_then(|s| s.split_once('(')) {
+			Some((fixed, repetend)) => (fixed, Some(repetend)),
+			None => (s, None),
+		};
+		let (integer, fraction) = fixed.split_once('.').unwrap_or((fixed, ""));
+		if integer.is_empty() && fraction.is_empty() {
+			return Err(format!("Missing digits in base {} number '{}'", radix, s));
+		}
+		if fraction.contains('.') {
+			return Err("Multiple radix points".to_string());
+		}
+
+		// The digits after the point count in units of radix^-k
+		let unit = BigInt::from(radix).pow(fraction.len() as u32);
+		let mut value = Rational::new(digits_value(&format!("{}{}", integer, fraction), radix)?, unit.clone())?;
+		if let Some(repetend) = repetend {
+			if repetend.is_empty() || !fixed.contains('.') {
+				return Err(format!("Repeating digits must follow the point, got '{}'", s));
+			}
+			// As in decimal, the repetend r adds r / (radix^k (radix^n - 1)) for n repeating digits
+			let period = BigInt::from(radix).pow(repetend.len() as u32) - 1;
+			value = value.add(&Rational::new(digits_value(repetend, radix)?, unit * period)?);
+		}
+		Ok(value)
+	}
+
+	// A number in the context's base, with its repetend in parentheses when that fits the
+	// display. Otherwise the digits after the point are rounded to about the precision,
+	// or fewer if the display is too narrow for them.
+	pub fn fraction_to_radix_string(value: &Rational, ctx: &Context) -> String {
+		let radix = ctx.radix;
+		if !value.is_integer()
+			&& let Some(expansion) = repeating_expansion(value, radix, ctx.display_limit) {
+			return expansion;
+		}
+
+		let whole = (value.numerator() / value.denominator()).abs().to_str_radix(radix);
+		let sign = if value.is_negative() { 1 } else { 0 };
+		let width = sign + radix_prefix(radix).len() + whole.len();
+		// Scientific notation is only available in decimal
+		if width > ctx.display_limit {
+			return value.to_decimal(ctx).to_display_string(ctx);
+		}
+
+		let places = (ctx.precision.max(0) as f64 * LN_10 / (radix as f64).ln()).ceil() as usize;
+		let places = places.min(ctx.display_limit.saturating_sub(width + 1));
+		let scaled = divide_rounded(
+			&(value.numerator() * BigInt::from(radix).pow(places as u32)),
+			value.denominator(),
+			ctx.rounding,
+		);
+		let digits = format!("{:0>width$}", scaled.abs().to_str_radix(radix).to_uppercase(), width = places + 1);
+		let (whole, fraction) = digits.split_at(digits.len() - places);
+		let fraction = fraction.trim_end_matches('0');
+		let sign = if scaled.is_negative() { "-" } else { "" };
+		if fraction.is_empty() {
+			format!("{}{}{}", sign, radix_prefix(radix), whole)
+		} else {
+			format!("{}{}{}.{}", sign, radix_prefix(radix), whole, fraction)
+		}
+	}
+}
+
+// How a number in the given base starts, so that results can be read back as input
+pub fn radix_prefix(radix: u32) -> String {
+	match radix {
+		2 => "0b".to_string(),
+		8 => "0o".to_string(),
+		10 => String::new(),
+		16 => "0x".to_string(),
+		_ => format!("{}#", radix),
+	}
+}
+
+// Value of a string of digits in the given base, in either case; empty is zero
+fn digits_value(digits: &str, radix: u32) -> Result<BigInt, String> {
+	let mut value = BigInt::zero();
+	for c in digits.chars() {
+		let digit = c.to_digit(radix).ok_or_else(|| format!("'{}' is not a digit in base {}", c, radix))?;
+		value = value * radix + digit;
+	}
+	Ok(value)
+}
@@ -0,0 +1,265 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::Ordering;
+use std::fmt;
+
+use super::{BigNumber, RoundingMode, check_result_digits, digit_count, divide_rounded, pow10};
+use super::number_theory::gcd;
+use crate::context::Context;
+
+// Exact fraction in lowest terms with a positive denominator
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rational {
+	numerator: BigInt,
+	denominator: BigInt,
+}
+
+impl Rational {
+	pub fn new(numerator: BigInt, denominator: BigInt) -> Result<Self, String> {
+		if denominator.is_zero() {
+			return Err("Division by zero".to_string());
+		}
+
+		let divisor = gcd(&numerator, &denominator);
+		let (numerator, denominator) = if denominator.is_negative() {
+			(-numerator / &divisor, -denominator / &divisor)
+		} else {
+			(numerator / &divisor, denominator / &divisor)
+		};
+		Ok(Self { numerator, denominator })
+	}
+
+	pub fn from_integer(value: BigInt) -> Self {
+		Self { numerator: value, denominator: BigInt::one() }
+	}
+
+	pub fn numerator(&self) -> &BigInt {
+		&self.numerator
+	}
+
+	pub fn denominator(&self) -> &BigInt {
+		&self.denominator
+	}
+
+	pub fn is_integer(&self) -> bool {
+		self.denominator.is_one()
+	}
+
+	pub fn is_zero(&self) -> bool {
+		self.numerator.is_zero()
+	}
+
+	pub fn is_negative(&self) -> bool {
+		self.numerator.is_negative()
+	}
+
+	pub fn negate(&self) -> Self {
+		Self { numerator: -&self.numerator, denominator: self.denominator.clone() }
+	}
+
+	pub fn add(&self, other: &Self) -> Self {
+		let numerator = &self.numerator * &other.denominator + &other.numerator * &self.denominator;
+		Self::reduced(numerator, &self.denominator * &other.denominator)
+	}
+
+	pub fn subtract(&self, other: &Self) -> Self {
+		self.add(&other.negate())
+	}
+
+	pub fn multiply(&self, other: &Self) -> Self {
+		Self::reduced(&self.numerator * &other.numerator, &self.denominator * &other.denominator)
+	}
+
+	pub fn divide(&self, other: &Self) -> Result<Self, String> {
+		Self::new(&self.numerator * &other.denominator, &self.denominator * &other.numerator)
+	}
+
+	// Largest integer not above self / other
+	pub fn floor_divide(&self, other: &Self) -> Result<BigInt, String> {
+		if other.is_zero() {
+			return Err("Division by zero".to_string());
+		}
+		Ok(floor_divide(&(&self.numerator * &other.denominator), &(&self.denominator * &other.numerator)))
+	}
+
+	// self - other * floor(self / other), which has the sign of other
+	pub fn modulo(&self, other: &Self) -> Result<Self, String> {
+		let quotient = Self::from_integer(self.floor_divide(other)?);
+		Ok(self.subtract(&other.multiply(&quotient)))
+	}
+
+	// self - other * trunc(self / other), which has the sign of self
+	pub fn remainder(&self, other: &Self) -> Result<Self, String> {
+		if other.is_zero() {
+			return Err("Division by zero".to_string());
+		}
+		let quotient = (&self.numerator * &other.denominator) / (&self.denominator * &other.numerator);
+		Ok(self.subtract(&other.multiply(&Self::from_integer(quotient))))
+	}
+
+	pub fn power(&self, exponent: &BigInt) -> Result<Self, String> {
+		if exponent.is_zero() {
+			return Ok(Self::from_integer(BigInt::one()));
+		}
+		if self.is_zero() {
+			if exponent.is_negative() {
+				return Err("Zero cannot be raised to a negative power".to_string());
+			}
+			return Ok(self.clone());
+		}
+
+		let base = if exponent.is_negative() { Self::new(self.denominator.clone(), self.numerator.clone())? } else { self.clone() };
+		let exponent = exponent.abs();
+		if base.numerator.abs().is_one() && base.denominator.is_one() {
+			// (+-1)^n only depends on the parity of n
+			let odd = !(&exponent & BigInt::one()).is_zero();
+			return Ok(if odd { base } else { Self::from_integer(BigInt::one()) });
+		}
+
+		// Both parts grow to |part|^n, which must stay within MAX_RESULT_DIGITS
+		let exp_f64 = exponent.to_f64().unwrap_or(f64::INFINITY);
+		let largest = if base.numerator.abs() > base.denominator { base.numerator.abs() } else { base.denominator.clone() };
+		check_result_digits(exp_f64 * BigNumber::new(largest, 0).log10_estimate())?;
+		let exponent = exponent.to_u32().ok_or("Exponent too large")?;
+
+		// Powers of a fraction in lowest terms stay in lowest terms
+		Ok(Self { numerator: base.numerator.pow(exponent), denominator: base.denominator.pow(exponent) })
+	}
+
+	// Rounded to the context precision, like the result of a decimal division
+	pub fn to_decimal(&self, ctx: &Context) -> BigNumber {
+		let scaled = &self.numerator * pow10(ctx.precision as u32);
+		let mantissa = divide_rounded(&scaled, &self.denominator, ctx.rounding);
+		BigNumber::new(mantissa, ctx.precision).normalize()
+	}
+
+	// Rounded to about `digits` significant digits however small it is, for tiny quantities
+	// like rounding errors that a fixed number of places would round to zero
+	pub fn to_significant(&self, digits: u32, rounding: RoundingMode) -> BigNumber {
+		if self.is_zero() {
+			return BigNumber::new(BigInt::zero(), 0);
+		}
+		let magnitude = digit_count(&self.numerator) as i32 - digit_count(&self.denominator) as i32;
+		let scale = digits as i32 - magnitude;
+		let mantissa = if scale >= 0 {
+			divide_rounded(&(&self.numerator * pow10(scale as u32)), &self.denominator, rounding)
+		} else {
+			divide_rounded(&self.numerator, &(&self.denominator * pow10((-scale) as u32)), rounding)
+		};
+		BigNumber::new(mantissa, scale).normalize()
+	}
+
+	// Terms [a0; a1, a2, ...] of the continued fraction, at most `max_terms` of them.
+	// The expansion of a fraction is always finite.
+	pub fn continued_fraction(&self, max_terms: usize) -> Vec<BigInt> {
+		let mut terms = Vec::new();
+		let mut numerator = self.numerator.clone();
+		let mut denominator = self.denominator.clone();
+		while !denominator.is_zero() && terms.len() < max_terms {
+			let term = floor_divide(&numerator, &denominator);
+			let remainder = &numerator - &term * &denominator;
+			terms.push(term);
+			numerator = denominator;
+			denominator = remainder;
+		}
+		terms
+	}
+
+	// The convergents with denominators up to `max_denominator`, each closer than the one
+	// before. When a semiconvergent within the bound is closer still, it ends the list,
+	// so the last entry is always the best approximation with such a denominator.
+	pub fn best_approximations(&self, max_denominator: &BigInt) -> Vec<Rational> {
+		let mut approximations: Vec<Rational> = Vec::new();
+		let (mut p0, mut q0) = (BigInt::zero(), BigInt::one());
+		let (mut p1, mut q1) = (BigInt::one(), BigInt::zero());
+		let mut numerator = self.numerator.clone();
+		let mut denominator = self.denominator.clone();
+
+		while !denominator.is_zero() {
+			let term = floor_divide(&numerator, &denominator);
+			let q2 = &q0 + &term * &q1;
+			if &q2 > max_denominator {
+				// (p0 + k p1) / (q0 + k q1) for the largest k that fits the bound
+				let k = (max_denominator - &q0) / &q1;
+				let last = approximations.last();
+				if !k.is_zero() && let Some(last) = last {
+					let candidate = Self::reduced(&p0 + &k * &p1, &q0 + &k * &q1);
+					if self.subtract(&candidate).abs() < self.subtract(last).abs() {
+						approximations.push(candidate);
+					}
+				}
+				break;
+			}
+
+			// Consecutive convergents are already in lowest terms
+			let p2 = &p0 + &term * &p1;
+			(p0, q0, p1, q1) = (p1, q1, p2, q2);
+			approximations.push(Self { numerator: p1.clone(), denominator: q1.clone() });
+
+			let remainder = &numerator - &term * &denominator;
+			numerator = denominator;
+			denominator = remainder;
+		}
+		approximations
+	}
+
+	pub fn abs(&self) -> Self {
+		Self { numerator: self.numerator.abs(), denominator: self.denominator.clone() }
+	}
+
+	fn reduced(numerator: BigInt, denominator: BigInt) -> Self {
+		let divisor = gcd(&numerator, &denominator);
+		Self { numerator: numerator / &divisor, denominator: denominator / &divisor }
+	}
+}
+
+impl PartialOrd for Rational {
+	fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+		// Denominators are positive, so cross-multiplying keeps the order
+		Some((&self.numerator * &other.denominator).cmp(&(&other.numerator * &self.denominator)))
+	}
+}
+
+impl fmt::Display for Rational {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		if self.is_integer() {
+			write!(f, "{}", self.numerator)
+		} else {
+			write!(f, "{}/{}", self.numerator, self.denominator)
+		}
+	}
+}
+
+impl BigNumber {
+	// The exact value of a decimal as a fraction
+	pub fn to_rational(&self) -> Rational {
+		if self.scale >= 0 {
+			Rational::reduced(self.mantissa.clone(), pow10(self.scale as u32))
+		} else {
+			Rational::from_integer(&self.mantissa * pow10((-self.scale) as u32))
+		}
+	}
+
+	// The exact value, unless its exponent needs more digits than any result may have:
+	// a decimal stores 1e999999999 in a few bytes, but its fraction would spell out every zero
+	pub fn try_to_rational(&self) -> Result<Rational, String> {
+		check_result_digits(self.scale.unsigned_abs() as f64)?;
+		Ok(self.to_rational())
+	}
+
+	// Continued fraction of the decimal exactly as stored, so an inexact result like
+	// pi at 15 places only agrees with the true expansion for its first terms
+	pub fn continued_fraction(&self, max_terms: usize) -> Vec<BigInt> {
+		self.to_rational().continued_fraction(max_terms)
+	}
+
+	pub fn best_approximations(&self, max_denominator: &BigInt) -> Vec<Rational> {
+		self.to_rational().best_approximations(max_denominator)
+	}
+}
+
+// Quotient rounded toward negative infinity
+fn floor_divide(n: &BigInt, d: &BigInt) -> BigInt {
+	let quotient = n / d;
+	if n.is_negative() != d.is_negative() && !(n % d).is_zero() { quotient - 1 } else { quotient }
+}
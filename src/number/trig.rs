@@ -0,0 +1,328 @@
+use num_bigint::BigInt;
+use num_traits::{One, Signed, ToPrimitive, Zero};
+
+use super::constants::pi_fixed;
+use super::{BigNumber, RoundingMode, check_result_digits, decimal_to_bits, divide_rounded, integer_root, pow10};
+use crate::context::{AngleUnit, Context};
+
+// Arcseconds in half a turn
+const HALF_TURN_ARCSECONDS: u32 = 648_000;
+
+// Trigonometric functions in the context's angle unit, evaluated in binary fixed point
+impl BigNumber {
+	pub fn sin(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		let bits = working_bits(ctx);
+		let (sin, _) = self.sin_cos_fixed(ctx.angle_unit, bits)?;
+		Ok(Self::from_fixed(&sin, bits, ctx))
+	}
+
+	pub fn cos(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Ok(Self::new(BigInt::one(), 0));
+		}
+		let bits = working_bits(ctx);
+		let (_, cos) = self.sin_cos_fixed(ctx.angle_unit, bits)?;
+		Ok(Self::from_fixed(&cos, bits, ctx))
+	}
+
+	pub fn tan(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		self.trig_quotient("tan", ctx, |sin, cos, _| (sin, cos))
+	}
+
+	pub fn sec(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Ok(Self::new(BigInt::one(), 0));
+		}
+		self.trig_quotient("sec", ctx, |_, cos, one| (one, cos))
+	}
+
+	pub fn csc(&self, ctx: &Context) -> Result<Self, String> {
+		self.trig_quotient("csc", ctx, |sin, _, one| (one, sin))
+	}
+
+	pub fn cot(&self, ctx: &Context) -> Result<Self, String> {
+		self.trig_quotient("cot", ctx, |sin, cos, _| (cos, sin))
+	}
+
+	pub fn asin(&self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() {
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		let bits = working_bits(ctx);
+		let asin = radians_to_unit(self.asin_fixed(bits)?, ctx.angle_unit, bits);
+		Ok(Self::from_fixed(&asin, bits, ctx))
+	}
+
+	pub fn acos(&self, ctx: &Context) -> Result<Self, String> {
+		if self.normalize() == Self::new(BigInt::one(), 0) {
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+		// acos(x) = pi/2 - asin(x)
+		let bits = working_bits(ctx);
+		let asin = self.asin_fixed(bits + 2)?;
+		let acos = radians_to_unit((pi_fixed(bits + 2) >> 1) - asin, ctx.angle_unit, bits + 2);
+		Ok(Self::from_fixed(&(acos >> 2), bits, ctx))
+	}
+
+	pub fn atan(&self, ctx: &Context) -> Result<Self, String> {
+		self.atan2(&Self::new(BigInt::one(), 0), ctx)
+	}
+
+	// Angle of the point (x, y) where self is y, in (-pi, pi]
+	pub fn atan2(&self, x: &Self, ctx: &Context) -> Result<Self, String> {
+		if self.is_zero() && x.is_zero() {
+			return Err("atan2(0, 0) is undefined".to_string());
+		}
+		if self.is_zero() && !x.is_negative() {
+			return Ok(Self::new(BigInt::zero(), 0));
+		}
+
+		// Bring both coordinates to a common scale so the ratio is exact
+		let common = self.scale.max(x.scale);
+		let y_int = &self.mantissa * pow10((common - self.scale) as u32);
+		let x_int = &x.mantissa * pow10((common - x.scale) as u32);
+
+		let bits = working_bits(ctx);
+		let angle = radians_to_unit(atan2_fixed(&y_int, &x_int, bits), ctx.angle_unit, bits);
+		Ok(Self::from_fixed(&angle, bits, ctx))
+	}
+
+	// Angle written in degrees, minutes and seconds, given as a total of arcseconds
+	pub fn from_arcseconds(arcseconds: &Self, ctx: &Context) -> Result<Self, String> {
+		match ctx.angle_unit.half_turn() {
+			Some(half_turn) => {
+				let per_unit = Self::new(BigInt::from(HALF_TURN_ARCSECONDS / half_turn), 0);
+				arcseconds.divide(&per_unit, ctx)
+			}
+			None => {
+				let bits = working_bits(ctx);
+				let scaled = (arcseconds.to_fixed(bits) * pi_fixed(bits)) >> bits;
+				Ok(Self::from_fixed(&(scaled / HALF_TURN_ARCSECONDS), bits, ctx))
+			}
+		}
+	}
+
+	// Formats an angle in the context's unit as degrees, minutes and seconds (12°30'15")
+	pub fn to_dms_string(&self, ctx: &Context) -> Result<String, String> {
+		let magnitude = self.log10_estimate().max(0.0);
+		check_result_digits(magnitude)?;
+
+		// A radian holds about 2*10^5 arcseconds, so the seconds keep six fewer places
+		let places = (ctx.precision - 6).max(0);
+		let arcseconds = match ctx.angle_unit.half_turn() {
+			Some(half_turn) => {
+				let per_unit = Self::new(BigInt::from(HALF_TURN_ARCSECONDS / half_turn), 0);
+				self.multiply(&per_unit).scale_to(places, ctx.rounding)
+			}
+			None => {
+				let bits = decimal_to_bits(places as f64 + magnitude + 6.0) + 16;
+				let scaled = ((self.to_fixed(bits) * HALF_TURN_ARCSECONDS) << bits) / pi_fixed(bits);
+				Self::from_fixed(&scaled, bits, &ctx.with_precision(places)).scale_to(places, RoundingMode::Down)
+			}
+		};
+
+		let sign = if arcseconds.is_negative() { "-" } else { "" };
+		let unit = pow10(places as u32);
+		let whole = arcseconds.mantissa.abs() / &unit;
+		let fraction = arcseconds.mantissa.abs() % &unit;
+		let seconds = Self::new((&whole % 60u32) * &unit + fraction, places).normalize();
+		Ok(format!("{}{}°{}'{}\"", sign, &whole / 3600u32, (&whole / 60u32) % 60u32, seconds.to_standard_string()))
+	}
+
+	// sin and cos together, each within a few units of 2^-bits
+	fn sin_cos_fixed(&self, unit: AngleUnit, bits: u64) -> Result<(BigInt, BigInt), String> {
+		let magnitude = self.log10_estimate().max(0.0);
+		check_result_digits(magnitude)?;
+
+		// Each angle doubling at the end doubles the error, so it costs one guard bit
+		let halvings = (bits as f64).sqrt() as u64 / 2 + 1;
+		let work = bits + halvings + 16;
+		let one = BigInt::one() << work;
+
+		// x = k quarter turns + r with |r| at most an eighth of a turn
+		let (k, r) = match unit.half_turn() {
+			None => {
+				// Reducing a huge argument needs pi to as many digits as the argument has
+				let reduction_work = work + decimal_to_bits(magnitude);
+				let x = self.to_fixed(reduction_work);
+				let half_pi: BigInt = pi_fixed(reduction_work) >> 1;
+				let k = divide_rounded(&x, &half_pi, RoundingMode::HalfEven);
+				let r = (x - &k * &half_pi) >> (reduction_work - work);
+				(k, r)
+			}
+			Some(half_turn) => {
+				// Exact in decimal, so multiples of a quarter turn land exactly on an axis
+				let x = self.scale_to(self.scale.max(0), RoundingMode::Down);
+				let quarter = BigInt::from(half_turn / 2);
+				let k = divide_rounded(&x.mantissa, &(&quarter * pow10(x.scale as u32)), RoundingMode::HalfEven);
+				let r = x.subtract(&Self::new(&k * &quarter, 0));
+				(k, ((r.to_fixed(work) * pi_fixed(work)) >> work) / half_turn)
+			}
+		};
+		let r = r >> halvings;
+		let quadrant = ((k % 4u32) + 4u32) % 4u32;
+
+		// Taylor series for sin and cos of the reduced angle
+		let r_squared = (&r * &r) >> work;
+		let mut sin = r.clone();
+		let mut cos = one.clone();
+		let mut sin_term = r;
+		let mut cos_term = one;
+		let mut n = 1u64;
+		loop {
+			cos_term = -((cos_term * &r_squared) >> work) / ((2 * n - 1) * (2 * n));
+			sin_term = -((sin_term * &r_squared) >> work) / ((2 * n) * (2 * n + 1));
+			if cos_term.is_zero() && sin_term.is_zero() {
+				break;
+			}
+			cos += &cos_term;
+			sin += &sin_term;
+			n += 1;
+		}
+
+		// Double the angle back up: sin 2a = 2 sin a cos a, cos 2a = cos^2 a - sin^2 a
+		for _ in 0..halvings {
+			let next_sin = (&sin * &cos) >> (work - 1);
+			cos = (&cos * &cos - &sin * &sin) >> work;
+			sin = next_sin;
+		}
+
+		let (sin, cos) = match quadrant.to_u32() {
+			Some(0) => (sin, cos),
+			Some(1) => (cos, -sin),
+			Some(2) => (-sin, -cos),
+			_ => (-cos, sin),
+		};
+		let shift = work - bits;
+		Ok((sin >> shift, cos >> shift))
+	}
+
+	// numerator / denominator of sin and cos, retried at higher precision when the
+	// denominator is close to zero and its error would dominate the quotient
+	fn trig_quotient(
+		&self,
+		name: &str,
+		ctx: &Context,
+		pick: fn(BigInt, BigInt, BigInt) -> (BigInt, BigInt),
+	) -> Result<Self, String> {
+		let target = decimal_to_bits(ctx.precision as f64) + 8;
+		let limit = target * 16 + 4096;
+		let mut bits = target + 32;
+
+		loop {
+			let (sin, cos) = self.sin_cos_fixed(ctx.angle_unit, bits)?;
+			let (numerator, denominator) = pick(sin, cos, BigInt::one() << bits);
+
+			if denominator.is_zero() {
+				// Only an exact quarter turn in degrees or gradians hits the pole itself
+				return Err(format!("{} is undefined at this angle", name));
+			}
+			if denominator.bits() > 8 {
+				// The quotient error grows with 1/denominator^2
+				let deficit = bits.saturating_sub(denominator.bits());
+				let required = target + 2 * deficit + 16;
+				if bits >= required {
+					let scaled = numerator * pow10(ctx.precision as u32);
+					let quotient = divide_rounded(&scaled, &denominator, ctx.rounding);
+					return Ok(Self::new(quotient, ctx.precision).normalize());
+				}
+				bits = required;
+			} else {
+				bits *= 2;
+			}
+
+			if bits > limit {
+				return Err(format!("{} is undefined this close to a pole", name));
+			}
+		}
+	}
+
+	fn asin_fixed(&self, bits: u64) -> Result<BigInt, String> {
+		let one = Self::new(BigInt::one(), 0);
+		let complement = one.subtract(&self.multiply(self));
+		if complement.is_negative() {
+			return Err("Argument must be between -1 and 1".to_string());
+		}
+
+		// asin(x) = atan2(x, sqrt(1 - x^2)); 1 - x^2 is exact, but a small value needs
+		// extra bits so its square root keeps full relative precision
+		let small = if complement.is_zero() { 0.0 } else { -complement.log10_estimate() };
+		let work = bits + decimal_to_bits(small) + 16;
+		let y = self.to_fixed(work);
+		let x = integer_root(&complement.to_fixed(2 * work), 2);
+		Ok(atan2_fixed(&y, &x, bits))
+	}
+}
+
+fn working_bits(ctx: &Context) -> u64 {
+	decimal_to_bits(ctx.precision as f64) + 16
+}
+
+// An angle in radians expressed in `unit`, both in fixed point with `bits` fractional bits
+fn radians_to_unit(angle: BigInt, unit: AngleUnit, bits: u64) -> BigInt {
+	match unit.half_turn() {
+		Some(half_turn) => ((angle * half_turn) << bits) / pi_fixed(bits),
+		None => angle,
+	}
+}
+
+// atan2 of two integers that share a scale, not both zero
+fn atan2_fixed(y: &BigInt, x: &BigInt, bits: u64) -> BigInt {
+	let work = bits + 8;
+	let half_pi: BigInt = pi_fixed(work) >> 1;
+
+	if x.is_zero() {
+		let angle = if y.is_negative() { -half_pi } else { half_pi };
+		return angle >> 8;
+	}
+
+	// Keep the series argument within [-1, 1]: atan(y/x) = +-pi/2 - atan(x/y)
+	let angle = if y.abs() <= x.abs() {
+		atan_fixed(&((y << work) / x), work)
+	} else {
+		let complement = atan_fixed(&((x << work) / y), work);
+		if y.is_negative() == x.is_negative() { half_pi.clone() - complement } else { -half_pi.clone() - complement }
+	};
+
+	let angle = if x.is_negative() {
+		if y.is_negative() { angle - (half_pi << 1) } else { angle + (half_pi << 1) }
+	} else {
+		angle
+	};
+	angle >> 8
+}
+
+// atan(t) for |t| <= 1, halving the argument with t / (1 + sqrt(1 + t^2)) first
+fn atan_fixed(t: &BigInt, bits: u64) -> BigInt {
+	let halvings = (bits as f64).sqrt() as u64 / 2 + 1;
+	let work = bits + halvings + 8;
+	let one = BigInt::one() << work;
+
+	let mut t = t << (work - bits);
+	for _ in 0..halvings {
+		let root = integer_root(&((&one + ((&t * &t) >> work)) << work), 2);
+		t = (t << work) / (&one + root);
+	}
+
+	let t_squared = (&t * &t) >> work;
+	let mut sum = BigInt::zero();
+	let mut power = t;
+	let mut n = 1u64;
+	loop {
+		let term = &power / n;
+		if term.is_zero() {
+			break;
+		}
+		sum += term;
+		power = -((power * &t_squared) >> work);
+		n += 2;
+	}
+
+	(sum << halvings) >> (work - bits)
+}
@@ -0,0 +1,314 @@
+use num_bigint::BigInt;
+
+use crate::lexer::Token;
+use crate::number::{BigNumber, Rational, parse_number, parse_radix, parse_repeating};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BinaryOp {
+	Add,
+	Subtract,
+	Multiply,
+	Divide,
+	FloorDivide,
+	Modulo,
+	Power,
+	Polar, // Modulus and angle of a complex number, as in 2∠45
+	BitAnd,
+	BitOr,
+	BitXor,
+	ShiftLeft,
+	ShiftRight,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnaryOp {
+	Negate,
+	BitNot,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+	Number(BigNumber),
+	Angle(BigNumber), // Degrees-minutes-seconds literal, in arcseconds
+	Fraction(Rational),
+	Imaginary(BigNumber),
+	Unary(UnaryOp, Box<Expr>),
+	Binary(BinaryOp, Box<Expr>, Box<Expr>),
+	Call(String, Vec<Expr>),
+	Constant(String),
+}
+
+// Prefix operators bind tighter than * and / but looser than ^, so "-2^2" is -(2^2)
+const PREFIX_BP: u8 = 13;
+
+// Postfix operators bind tightest of all, so "2^3!" is 2^(3!) and "-3!" is -(3!)
+const POSTFIX_BP: u8 = 17;
+
+// There are no tuples, so "(2, 3)" or "2, 3" is a mistake rather than an unclosed group
+const STRAY_COMMA: &str = "A comma is only allowed between function arguments";
+
+// Precedence-climbing (Pratt) parser over the token stream
+pub struct Parser {
+	tokens: Vec<Token>,
+	pos: usize,
+}
+
+impl Parser {
+	pub fn new(tokens: Vec<Token>) -> Self {
+		Self { tokens, pos: 0 }
+	}
+
+	pub fn parse(mut self) -> Result<Expr, String> {
+		let expr = self.parse_expr(0)?;
+		match self.peek() {
+			None => {}
+			Some(Token::RParen) => return Err("Unmatched ')' without an opening '('".to_string()),
+			Some(Token::Comma) => return Err(STRAY_COMMA.to_string()),
+			Some(token) => return Err(format!("Unexpected {} after expression", token.describe())),
+		}
+		Ok(expr)
+	}
+
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.pos)
+	}
+
+	fn advance(&mut self) -> Option<Token> {
+		let token = self.tokens.get(self.pos).cloned();
+		if token.is_some() {
+			self.pos += 1;
+		}
+		token
+	}
+
+	fn parse_expr(&mut self, min_bp: u8) -> Result<Expr, String> {
+		let mut lhs = self.parse_prefix()?;
+
+		while let Some(token) = self.peek() {
+			// A closing parenthesis or comma ends the current group; the caller decides if it is expected
+			if matches!(token, Token::RParen | Token::Comma) {
+				break;
+			}
+			if let Some(name) = postfix_function(token) {
+				if POSTFIX_BP < min_bp {
+					break;
+				}
+				self.advance();
+				lhs = Expr::Call(name.to_string(), vec![lhs]);
+				continue;
+			}
+			let Some((op, left_bp, right_bp)) = infix_binding_power(token) else {
+				return Err(format!("Expected an operator, found {}", token.describe()));
+			};
+			if left_bp < min_bp {
+				break;
+			}
+			self.advance();
+			let rhs = self.parse_expr(right_bp)?;
+			lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+		}
+
+		Ok(lhs)
+	}
+
+	fn parse_prefix(&mut self) -> Result<Expr, String> {
+		match self.advance() {
+			Some(Token::Number(literal)) => {
+				if literal.ends_with(')') {
+					return Ok(Expr::Fraction(parse_repeating(&literal)?));
+				}
+				Ok(Expr::Number(parse_number(&literal)?))
+			}
+			Some(Token::Dms(degrees, minutes, seconds)) => {
+				Ok(Expr::Angle(dms_to_arcseconds(&degrees, minutes.as_deref(), seconds.as_deref())?))
+			}
+			Some(Token::Radix(radix, digits)) => Ok(Expr::Fraction(parse_radix(&digits, radix)?)),
+			Some(Token::Imaginary(literal)) => Ok(Expr::Imaginary(parse_number(&literal)?)),
+			Some(Token::Fraction(whole, numerator, denominator)) => {
+				Ok(Expr::Fraction(parse_fraction(&whole, &numerator, &denominator)?))
+			}
+			Some(Token::LParen) => {
+				if self.peek() == Some(&Token::RParen) {
+					return Err("Empty parentheses".to_string());
+				}
+				let inner = self.parse_expr(0)?;
+				match self.advance() {
+					Some(Token::RParen) => Ok(inner),
+					Some(Token::Comma) => Err(STRAY_COMMA.to_string()),
+					_ => Err("Missing closing ')'".to_string()),
+				}
+			}
+			Some(Token::Minus) => {
+				let operand = self.parse_expr(PREFIX_BP)?;
+				Ok(Expr::Unary(UnaryOp::Negate, Box::new(operand)))
+			}
+			Some(Token::Plus) => self.parse_expr(PREFIX_BP),
+			Some(Token::BitNot) => {
+				let operand = self.parse_expr(PREFIX_BP)?;
+				Ok(Expr::Unary(UnaryOp::BitNot, Box::new(operand)))
+			}
+			Some(Token::Sqrt) => {
+				let operand = self.parse_expr(PREFIX_BP)?;
+				Ok(Expr::Call("sqrt".to_string(), vec![operand]))
+			}
+			Some(Token::Ident(name)) => {
+				// A name without an argument list is a constant like pi
+				if self.peek() != Some(&Token::LParen) {
+					return Ok(Expr::Constant(name));
+				}
+				self.advance();
+				let args = self.parse_arguments()?;
+				Ok(Expr::Call(name, args))
+			}
+			Some(token) => Err(format!("Expected a number, found {}", token.describe())),
+			None => Err("Unexpected end of expression".to_string()),
+		}
+	}
+
+	// Comma-separated arguments after the opening '(' of a call, up to and including ')'
+	fn parse_arguments(&mut self) -> Result<Vec<Expr>, String> {
+		let mut args = Vec::new();
+		if self.peek() == Some(&Token::RParen) {
+			self.advance();
+			return Ok(args);
+		}
+
+		loop {
+			args.push(self.parse_expr(0)?);
+			match self.advance() {
+				Some(Token::Comma) => continue,
+				Some(Token::RParen) => return Ok(args),
+				_ => return Err("Missing closing ')' in function call".to_string()),
+			}
+		}
+	}
+}
+
+// Returns the operator with its left and right binding powers.
+// A right binding power below the left one makes the operator right-associative.
+fn infix_binding_power(token: &Token) -> Option<(BinaryOp, u8, u8)> {
+	let entry = match token {
+		// Bitwise operators bind loosest, in the same order as in C and Python:
+		// 1 << 2 + 3 is 1 << 5 and 6 & 3 | 8 is (6 & 3) | 8
+		Token::BitOr => (BinaryOp::BitOr, 1, 2),
+		Token::Xor => (BinaryOp::BitXor, 3, 4),
+		Token::BitAnd => (BinaryOp::BitAnd, 5, 6),
+		Token::ShiftLeft => (BinaryOp::ShiftLeft, 7, 8),
+		Token::ShiftRight => (BinaryOp::ShiftRight, 7, 8),
+		// The angle of 2∠pi/2 is pi/2, while 1+2∠30 adds 1 to a complex number
+		Token::Plus => (BinaryOp::Add, 9, 10),
+		Token::Minus => (BinaryOp::Subtract, 9, 10),
+		Token::Star => (BinaryOp::Multiply, 11, 12),
+		Token::Slash => (BinaryOp::Divide, 11, 12),
+		Token::DoubleSlash => (BinaryOp::FloorDivide, 11, 12),
+		Token::Percent | Token::Mod => (BinaryOp::Modulo, 11, 12),
+		Token::Polar => (BinaryOp::Polar, 10, 11),
+		Token::Caret => (BinaryOp::Power, 15, 14),
+		_ => return None,
+	};
+	Some(entry)
+}
+
+// Postfix operators are shorthands for functions of their operand
+fn postfix_function(token: &Token) -> Option<&'static str> {
+	match token {
+		Token::Bang => Some("factorial"),
+		Token::DoubleBang => Some("double_factorial"),
+		_ => None,
+	}
+}
+
+// Value of a fraction literal; the whole part of a mixed number adds to it
+fn parse_fraction(whole: &str, numerator: &str, denominator: &str) -> Result<Rational, String> {
+	let parse_integer = |digits: &str| digits.parse::<BigInt>().map_err(|e| format!("Invalid number '{}': {}", digits, e));
+	let denominator = parse_integer(denominator)?;
+	let numerator = parse_integer(numerator)? + parse_integer(whole)? * &denominator;
+	Rational::new(numerator, denominator)
+}
+
+// Total arcseconds of an angle; minutes and seconds must stay below 60
+fn dms_to_arcseconds(degrees: &str, minutes: Option<&str>, seconds: Option<&str>) -> Result<BigNumber, String> {
+	let mut total = parse_number(degrees)?.multiply(&parse_number("3600")?);
+	let sixty = parse_number("60")?;
+	for (part, name, factor) in [(minutes, "Minutes", &sixty), (seconds, "Seconds", &parse_number("1")?)] {
+		if let Some(part) = part {
+			let value = parse_number(part)?;
+			if !value.subtract(&sixty).is_negative() {
+				return Err(format!("{} of an angle must be below 60, got {}", name, part));
+			}
+			total = total.add(&value.multiply(factor));
+		}
+	}
+	Ok(total)
+}
+
+pub fn parse(tokens: Vec<Token>) -> Result<Expr, String> {
+	Parser::new(tokens).parse()
+}
+
+#[cfg(test)]
+mod tests {
+	use crate::calculate;
+	use crate::context::Context;
+
+	fn eval(input: &str) -> String {
+		calculate(input, &Context::default())
+	}
+
+	#[test]
+	fn multiplication_binds_tighter_than_addition() {
+		assert_eq!(eval("1 + 2 * 3"), "7");
+		assert_eq!(eval("(1 + 2) * 3"), "9");
+		assert_eq!(eval("10 - 4 - 3"), "3");
+	}
+
+	#[test]
+	fn power_is_right_associative_and_above_prefix_minus() {
+		assert_eq!(eval("2^3^2"), "512");
+		assert_eq!(eval("-2^2"), "-4");
+		assert_eq!(eval("2^-1"), "0.5");
+	}
+
+	#[test]
+	fn postfix_operators_bind_tightest() {
+		assert_eq!(eval("2^3!"), "64");
+		assert_eq!(eval("-3!"), "-6");
+	}
+
+	#[test]
+	fn spacing_does_not_change_precedence() {
+		for (unspaced, spaced) in [("2^3/4", "2^3 / 4"), ("8/2!", "8 / 2!"), ("2^1/2", "2^1 / 2"), ("-2/3", "-2 / 3")] {
+			assert_eq!(eval(unspaced), eval(spaced), "{}", unspaced);
+		}
+		assert_eq!(eval("2^3/4"), "2");
+		assert_eq!(eval("8/2!"), "4");
+	}
+
+	#[test]
+	fn mixed_numbers_are_single_literals() {
+		assert_eq!(eval("1 3/4"), "1.75");
+		assert_eq!(eval("-1 3/4"), "-1.75");
+		assert_eq!(eval("2 * 1 1/2"), "3");
+	}
+
+	#[test]
+	fn bitwise_operators_bind_below_arithmetic() {
+		assert_eq!(eval("1 + 2 << 1"), "6");
+		assert_eq!(eval("6 & 3 | 8"), "10");
+		assert_eq!(eval("1 | 2 xor 3"), "1");
+	}
+
+	#[test]
+	fn unbalanced_parentheses_are_errors() {
+		assert!(eval("(1 + 2").starts_with("Error:"));
+		assert!(eval("1 + 2)").starts_with("Error:"));
+	}
+
+	#[test]
+	fn commas_outside_calls_are_errors() {
+		assert_eq!(eval("(2, 3)"), "Error: A comma is only allowed between function arguments");
+		assert_eq!(eval("2, 3"), "Error: A comma is only allowed between function arguments");
+		assert_eq!(eval("gcd((12, 18))"), "Error: A comma is only allowed between function arguments");
+		assert_eq!(eval("gcd(12, 18)"), "6");
+	}
+}
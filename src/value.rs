@@ -0,0 +1,294 @@
+use num_bigint::BigInt;
+use num_traits::Signed;
+
+use crate::context::Context;
+use crate::number::{BigNumber, Complex, Rational};
+
+// Result of evaluating an expression: exact while only rational operations are
+// involved, and a decimal at the context precision once anything else happens.
+// Complex values only appear once something has an imaginary part.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+	Exact(Rational),
+	Decimal(BigNumber),
+	Complex(Complex),
+	Tuple(Vec<Value>), // Several results at once, like the quotient and remainder of divmod()
+	Factored(Vec<(BigInt, u32)>), // Integer as prime powers from factor(), with -1 first when negative
+}
+
+impl Value {
+	// Literals stay exact unless exact arithmetic is turned off
+	pub fn from_literal(value: &BigNumber, ctx: &Context) -> Result<Self, String> {
+		if ctx.exact {
+			Ok(Value::Exact(value.try_to_rational()?))
+		} else {
+			Ok(Value::Decimal(value.clone()))
+		}
+	}
+
+	// Literals of fractions, like 1 3/4 or the repeating 0.(3), are exact in the same way
+	pub fn from_fraction(value: &Rational, ctx: &Context) -> Self {
+		if ctx.exact {
+			Value::Exact(value.clone())
+		} else {
+			Value::Decimal(value.to_decimal(ctx))
+		}
+	}
+
+	// A complex result whose imaginary part cancelled out is an ordinary decimal again
+	pub fn from_complex(value: Complex) -> Self {
+		if value.is_real() {
+			Value::Decimal(value.re().clone())
+		} else {
+			Value::Complex(value)
+		}
+	}
+
+	// A factorization stands for its product, as an exact integer when exact arithmetic
+	// is on; other values are already plain numbers
+	pub fn expand(self, ctx: &Context) -> Self {
+		match self {
+			Value::Factored(factors) => Value::from_fraction(&Rational::from_integer(factor_product(&factors)), ctx),
+			value => value,
+		}
+	}
+
+	pub fn is_complex(&self) -> bool {
+		matches!(self, Value::Complex(_))
+	}
+
+	pub fn is_negative(&self) -> bool {
+		match self {
+			Value::Exact(value) => value.is_negative(),
+			Value::Decimal(value) => value.is_negative(),
+			Value::Factored(factors) => factors.first().is_some_and(|(prime, _)| prime.is_negative()),
+			Value::Complex(_) | Value::Tuple(_) => false,
+		}
+	}
+
+	pub fn is_integer(&self) -> bool {
+		match self {
+			Value::Exact(value) => value.is_integer(),
+			Value::Decimal(value) => value.is_integer(),
+			Value::Complex(value) => value.is_real() && value.re().is_integer(),
+			Value::Factored(_) => true,
+			Value::Tuple(_) => false,
+		}
+	}
+
+	pub fn to_decimal(&self, ctx: &Context) -> Result<BigNumber, String> {
+		match self {
+			Value::Complex(_) => Err("Expected a real number, got a complex one".to_string()),
+			_ => self.real_part(ctx),
+		}
+	}
+
+	pub fn to_complex(&self, ctx: &Context) -> Result<Complex, String> {
+		match self {
+			Value::Complex(value) => Ok(value.clone()),
+			_ => Ok(Complex::from_real(self.real_part(ctx)?)),
+		}
+	}
+
+	pub fn negate(&self) -> Result<Self, String> {
+		match self {
+			Value::Exact(value) => Ok(Value::Exact(value.negate())),
+			Value::Decimal(value) => Ok(Value::Decimal(value.negate())),
+			Value::Complex(value) => Ok(Value::Complex(value.negate())),
+			// Negating adds or removes the factor -1
+			Value::Factored(factors) => {
+				let mut factors = factors.clone();
+				if self.is_negative() {
+					factors.remove(0);
+				} else {
+					factors.insert(0, (BigInt::from(-1), 1));
+				}
+				Ok(Value::Factored(factors))
+			}
+			Value::Tuple(values) => Err(format!("Expected a single number, got {} results", values.len())),
+		}
+	}
+
+	pub fn add(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		match (self, other) {
+			(Value::Exact(a), Value::Exact(b)) => Ok(Value::Exact(a.add(b))),
+			(Value::Complex(_), _) | (_, Value::Complex(_)) => {
+				Ok(Value::from_complex(self.to_complex(ctx)?.add(&other.to_complex(ctx)?)))
+			}
+			_ => Ok(Value::Decimal(self.real_part(ctx)?.add(&other.real_part(ctx)?))),
+		}
+	}
+
+	pub fn subtract(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		match (self, other) {
+			(Value::Exact(a), Value::Exact(b)) => Ok(Value::Exact(a.subtract(b))),
+			(Value::Complex(_), _) | (_, Value::Complex(_)) => {
+				Ok(Value::from_complex(self.to_complex(ctx)?.subtract(&other.to_complex(ctx)?)))
+			}
+			_ => Ok(Value::Decimal(self.real_part(ctx)?.subtract(&other.real_part(ctx)?))),
+		}
+	}
+
+	pub fn multiply(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		match (self, other) {
+			(Value::Exact(a), Value::Exact(b)) => Ok(Value::Exact(a.multiply(b))),
+			(Value::Complex(_), _) | (_, Value::Complex(_)) => {
+				Ok(Value::from_complex(self.to_complex(ctx)?.multiply(&other.to_complex(ctx)?)))
+			}
+			_ => Ok(Value::Decimal(self.real_part(ctx)?.multiply(&other.real_part(ctx)?))),
+		}
+	}
+
+	pub fn divide(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		match (self, other) {
+			(Value::Exact(a), Value::Exact(b)) => Ok(Value::Exact(a.divide(b)?)),
+			(Value::Complex(_), _) | (_, Value::Complex(_)) => {
+				Ok(Value::from_complex(self.to_complex(ctx)?.divide(&other.to_complex(ctx)?, ctx)?))
+			}
+			_ => Ok(Value::Decimal(self.real_part(ctx)?.divide(&other.real_part(ctx)?, ctx)?)),
+		}
+	}
+
+	// Integer powers of a fraction are exact; any other exponent needs ln and exp,
+	// and a negative base with such an exponent has a complex principal value
+	pub fn power(&self, exponent: &Self, ctx: &Context) -> Result<Self, String> {
+		match (self, exponent) {
+			(Value::Exact(base), Value::Exact(exponent)) if exponent.is_integer() => {
+				Ok(Value::Exact(base.power(exponent.numerator())?))
+			}
+			_ => {
+				// Exact operands like the 1/3 in 8^(1/3) get guard digits before the logarithm
+				let work = ctx.with_precision(ctx.precision + 10);
+				if self.is_complex() || exponent.is_complex() || (self.is_negative() && !exponent.is_integer()) {
+					Ok(Value::from_complex(self.to_complex(&work)?.power(&exponent.to_complex(&work)?, ctx)?))
+				} else {
+					Ok(Value::Decimal(self.real_part(&work)?.power(&exponent.real_part(&work)?, ctx)?))
+				}
+			}
+		}
+	}
+
+	// Quotient rounded towards negative infinity, as in 7 // 2 = 3 and -7 // 2 = -4
+	pub fn floor_divide(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		let (a, b) = self.exact_operands(other, "Integer division")?;
+		let quotient = Rational::from_integer(a.floor_divide(&b)?);
+		Ok(self.with_exactness(other, quotient, ctx))
+	}
+
+	// Floored remainder, with the sign of the divisor: -7 mod 3 = 2
+	pub fn modulo(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		let (a, b) = self.exact_operands(other, "Modulo")?;
+		Ok(self.with_exactness(other, a.modulo(&b)?, ctx))
+	}
+
+	// Truncated remainder, with the sign of the dividend: rem(-7, 3) = -1
+	pub fn remainder(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		let (a, b) = self.exact_operands(other, "Remainder")?;
+		Ok(self.with_exactness(other, a.remainder(&b)?, ctx))
+	}
+
+	// Floored quotient and remainder together, so that a = q * b + r
+	pub fn divmod(&self, other: &Self, ctx: &Context) -> Result<Self, String> {
+		Ok(Value::Tuple(vec![self.floor_divide(other, ctx)?, self.modulo(other, ctx)?]))
+	}
+
+	// The point at distance `self` from the origin and `angle` from the positive real axis
+	pub fn polar(&self, angle: &Self, ctx: &Context) -> Result<Self, String> {
+		let modulus = self.to_decimal(ctx)?;
+		let angle = angle.to_decimal(ctx)?;
+		Ok(Value::from_complex(Complex::from_polar(&modulus, &angle, ctx)?))
+	}
+
+	pub fn continued_fraction(&self, max_terms: usize) -> Result<Vec<BigInt>, String> {
+		match self {
+			Value::Exact(value) => Ok(value.continued_fraction(max_terms)),
+			Value::Decimal(value) => Ok(value.continued_fraction(max_terms)),
+			Value::Factored(factors) => Ok(vec![factor_product(factors)]),
+			Value::Complex(_) => Err("Complex results have no continued fraction".to_string()),
+			Value::Tuple(_) => Err("Only single results have a continued fraction".to_string()),
+		}
+	}
+
+	pub fn best_approximations(&self, max_denominator: &BigInt) -> Result<Vec<Rational>, String> {
+		match self {
+			Value::Exact(value) => Ok(value.best_approximations(max_denominator)),
+			Value::Decimal(value) => Ok(value.best_approximations(max_denominator)),
+			Value::Factored(factors) => Ok(vec![Rational::from_integer(factor_product(factors))]),
+			Value::Complex(_) => Err("Complex results have no rational approximations".to_string()),
+			Value::Tuple(_) => Err("Only single results have rational approximations".to_string()),
+		}
+	}
+
+	// The value exactly as a fraction, for comparing it with the floating-point formats
+	pub fn to_rational(&self) -> Result<Rational, String> {
+		match self {
+			Value::Exact(value) => Ok(value.clone()),
+			Value::Decimal(value) => Ok(value.to_rational()),
+			Value::Factored(factors) => Ok(Rational::from_integer(factor_product(factors))),
+			Value::Complex(_) => Err("Complex results have no floating-point value".to_string()),
+			Value::Tuple(_) => Err("Only single results have a floating-point value".to_string()),
+		}
+	}
+
+	pub fn to_display_string(&self, ctx: &Context) -> String {
+		match self {
+			// Real numbers can be shown in other bases; complex ones and factorizations stay decimal
+			Value::Exact(value) if ctx.radix != 10 => BigNumber::fraction_to_radix_string(value, ctx),
+			Value::Decimal(value) if ctx.radix != 10 => BigNumber::fraction_to_radix_string(&value.to_rational(), ctx),
+			Value::Exact(value) => BigNumber::fraction_to_string(value, ctx),
+			Value::Decimal(value) => value.to_display_string(ctx),
+			Value::Complex(value) => value.to_display_string(ctx),
+			Value::Tuple(values) => {
+				let parts: Vec<String> = values.iter().map(|value| value.to_display_string(ctx)).collect();
+				format!("({})", parts.join(", "))
+			}
+			// Like 2^3 · 3 · 7; the empty factorization of 1 shows as 1
+			Value::Factored(factors) if factors.is_empty() => "1".to_string(),
+			Value::Factored(factors) => {
+				let parts: Vec<String> = factors.iter()
+					.map(|(prime, exponent)| match exponent {
+						1 => prime.to_string(),
+						_ => format!("{}^{}", prime, exponent),
+					})
+					.collect();
+				parts.join(" · ")
+			}
+		}
+	}
+
+	// The value itself for real numbers, the real part of a complex one
+	fn real_part(&self, ctx: &Context) -> Result<BigNumber, String> {
+		match self {
+			Value::Exact(value) => Ok(value.to_decimal(ctx)),
+			Value::Decimal(value) => Ok(value.clone()),
+			Value::Complex(value) => Ok(value.re().clone()),
+			Value::Factored(factors) => Ok(Rational::from_integer(factor_product(factors)).to_decimal(ctx)),
+			Value::Tuple(values) => Err(format!("Expected a single number, got {} results", values.len())),
+		}
+	}
+
+	// Both operands as exact fractions, for operations that need the exact quotient
+	fn exact_operands(&self, other: &Self, operation: &str) -> Result<(Rational, Rational), String> {
+		let exact = |value: &Self| match value {
+			Value::Exact(value) => Ok(value.clone()),
+			Value::Decimal(value) => Ok(value.to_rational()),
+			Value::Factored(factors) => Ok(Rational::from_integer(factor_product(factors))),
+			Value::Complex(_) => Err(format!("{} needs real numbers, got a complex one", operation)),
+			Value::Tuple(values) => Err(format!("Expected a single number, got {} results", values.len())),
+		};
+		Ok((exact(self)?, exact(other)?))
+	}
+
+	// Exact results stay exact when both operands were; decimal operands give a decimal
+	fn with_exactness(&self, other: &Self, result: Rational, ctx: &Context) -> Self {
+		match (self, other) {
+			(Value::Exact(_), Value::Exact(_)) => Value::Exact(result),
+			_ => Value::Decimal(result.to_decimal(ctx)),
+		}
+	}
+}
+
+// The integer a factorization stands for
+fn factor_product(factors: &[(BigInt, u32)]) -> BigInt {
+	factors.iter().map(|(prime, exponent)| prime.pow(*exponent)).product()
+}